@@ -0,0 +1,252 @@
+//! Accounts, sessions and the middleware guarding every write
+
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::cookie::{time, Cookie, SameSite};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::{header, Method};
+use actix_web::middleware::Next;
+use actix_web::{web, HttpMessage};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use rand_core::{OsRng, RngCore};
+use sea_orm::ActiveValue::Set;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter};
+use std::fmt::Write;
+use std::sync::LazyLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::AppError;
+use crate::server::AppState;
+use entity::account::{self, Role};
+use entity::profile::{self, Visibility};
+use entity::session;
+
+/// Name of the cookie holding the session token
+pub const SESSION_COOKIE: &str = "alumnimap_session";
+/// How long a session stays valid, in seconds
+pub const SESSION_LIFETIME: i64 = 7 * 24 * 60 * 60;
+
+/// Writes that can be made without logging in first
+const PUBLIC_WRITES: [&str; 2] = ["/api/login", "/api/v2/login"];
+
+/// Hashes a password with Argon2 and a random salt
+///
+/// # Errors
+/// Fails only if Argon2 rejects the input, e.g. the password is absurdly long.
+pub fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    Ok(Argon2::default()
+        .hash_password(password.as_bytes(), &salt)?
+        .to_string())
+}
+
+/// Hash checked instead when there is no such account, so that logging in takes as long
+static DUMMY_HASH: LazyLock<String> =
+    LazyLock::new(|| hash_password("no such account").unwrap_or_default());
+
+/// Checks a password against a hash made by [`hash_password`]
+#[must_use]
+pub fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash).is_ok_and(|parsed| {
+        Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok()
+    })
+}
+
+/// Gives back the account if the password is right
+///
+/// A password is checked even when there is no account, so the time taken does not tell whether
+/// the username exists.
+#[must_use]
+pub fn check_login(account: Option<account::Model>, password: &str) -> Option<account::Model> {
+    let Some(account) = account else {
+        let _ = verify_password(password, &DUMMY_HASH);
+        return None;
+    };
+    verify_password(password, &account.password_hash).then_some(account)
+}
+
+/// Current Unix timestamp in seconds
+#[must_use]
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs().try_into().unwrap_or(i64::MAX))
+        .unwrap_or_default()
+}
+
+/// Creates and stores a new session for the account
+///
+/// # Errors
+/// Returns the error if the session cannot be saved.
+pub async fn start_session(
+    db: &DatabaseConnection,
+    account_id: i32,
+) -> Result<session::Model, DbErr> {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let token = bytes.iter().fold(String::new(), |mut token, byte| {
+        let _ = write!(token, "{byte:02x}");
+        token
+    });
+    session::ActiveModel {
+        token: Set(token),
+        account_id: Set(account_id),
+        expires_at: Set(now() + SESSION_LIFETIME),
+    }
+    .insert(db)
+    .await
+}
+
+/// Builds the cookie carrying the session token
+#[must_use]
+pub fn session_cookie(session: &session::Model) -> Cookie<'static> {
+    Cookie::build(SESSION_COOKIE, session.token.clone())
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Strict)
+        .max_age(time::Duration::seconds(SESSION_LIFETIME))
+        .finish()
+}
+
+/// Builds a cookie telling the browser to forget the session token
+#[must_use]
+pub fn expired_cookie() -> Cookie<'static> {
+    let mut cookie = Cookie::build(SESSION_COOKIE, "").path("/").finish();
+    cookie.make_removal();
+    cookie
+}
+
+/// Finds the session and account a token belongs to, deleting the session if it has expired
+///
+/// # Errors
+/// Returns the error if the database cannot be queried.
+pub async fn find_session(
+    db: &DatabaseConnection,
+    token: &str,
+) -> Result<Option<(session::Model, account::Model)>, DbErr> {
+    let found = session::Entity::find_by_id(token)
+        .find_also_related(account::Entity)
+        .one(db)
+        .await?;
+    match found {
+        Some((session, Some(account))) if session.expires_at > now() => {
+            Ok(Some((session, account)))
+        }
+        Some((session, _)) => {
+            session::Entity::delete_by_id(session.token)
+                .exec(db)
+                .await?;
+            Ok(None)
+        }
+        None => Ok(None),
+    }
+}
+
+/// Deletes every expired session, returning how many there were
+///
+/// # Errors
+/// Returns the error if the database cannot be queried.
+pub async fn delete_expired_sessions(db: &DatabaseConnection) -> Result<u64, DbErr> {
+    let deleted = session::Entity::delete_many()
+        .filter(session::Column::ExpiresAt.lte(now()))
+        .exec(db)
+        .await?;
+    Ok(deleted.rows_affected)
+}
+
+/// Whether the account may change profiles of the given class
+#[must_use]
+pub fn can_edit_class(account: &account::Model, class_of: i32) -> bool {
+    match account.role {
+        Role::Admin => true,
+        Role::Editor => account.class_of == Some(class_of),
+        Role::Member => false,
+    }
+}
+
+/// Whether the viewer, if logged in at all, may see the contacts of a profile
+#[must_use]
+pub fn can_see_contacts(viewer: Option<&account::Model>, profile: &profile::Model) -> bool {
+    match profile.contact_visibility {
+        Visibility::Public => true,
+        Visibility::Classmates => viewer.is_some_and(|account| {
+            account.class_of == Some(profile.class_of) || can_edit_class(account, profile.class_of)
+        }),
+        Visibility::Hidden => {
+            viewer.is_some_and(|account| can_edit_class(account, profile.class_of))
+        }
+    }
+}
+
+/// Clears every contact the viewer is not allowed to see, marking the profile accordingly
+pub fn redact_contacts(viewer: Option<&account::Model>, profile: &mut profile::Model) {
+    if can_see_contacts(viewer, profile) {
+        return;
+    }
+    profile.contacts_hidden = [
+        &profile.email,
+        &profile.qq,
+        &profile.wechat,
+        &profile.matrix,
+    ]
+    .iter()
+    .any(|contact| contact.is_some());
+    profile.email = None;
+    profile.qq = None;
+    profile.wechat = None;
+    profile.matrix = None;
+}
+
+/// Attaches the logged in [`account::Model`] and [`session::Model`] to the request
+///
+/// Reading stays public, but every other method is refused with a 401 unless the request carries
+/// a valid session, either as a bearer token or in the [`SESSION_COOKIE`].
+///
+/// # Errors
+/// Fails if the database cannot be queried.
+pub async fn authenticate<B: MessageBody>(
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<EitherBody<B>>, actix_web::Error> {
+    if let Some(token) = token_of(&req) {
+        let state = req
+            .app_data::<web::Data<AppState>>()
+            .ok_or_else(|| AppError::Internal("App state missing".to_string()))?;
+        if let Some((session, account)) = find_session(&state.db, &token)
+            .await
+            .map_err(AppError::from)?
+        {
+            req.extensions_mut().insert(session);
+            req.extensions_mut().insert(account);
+        }
+    }
+
+    let reading = matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+    if !reading
+        && !PUBLIC_WRITES.contains(&req.path())
+        && !req.extensions().contains::<account::Model>()
+    {
+        return Ok(req
+            .error_response(AppError::Unauthorized("Login required"))
+            .map_into_right_body());
+    }
+    next.call(req)
+        .await
+        .map(ServiceResponse::map_into_left_body)
+}
+
+/// Reads the session token from the `Authorization` header, falling back to the cookie
+fn token_of(req: &ServiceRequest) -> Option<String> {
+    let bearer = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::to_string);
+    bearer.or_else(|| {
+        req.cookie(SESSION_COOKIE)
+            .map(|cookie| cookie.value().to_string())
+    })
+}
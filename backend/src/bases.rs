@@ -0,0 +1,156 @@
+//! Base points alumni set out from, one per school, campus or cohort
+//!
+//! Profiles without a base point belong to the default one, which is the oldest. The `[base]`
+//! section of the config only creates it when the database has none yet.
+
+use sea_orm::sea_query::{Condition, Expr};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, TransactionTrait,
+};
+use std::collections::BTreeMap;
+
+use crate::error::AppError;
+use crate::forms::{BaseForm, FieldError};
+use entity::{base, profile};
+
+/// The base point of profiles that name none
+///
+/// # Errors
+/// Returns the error if the database cannot be queried.
+pub async fn default_base(db: &DatabaseConnection) -> Result<Option<base::Model>, DbErr> {
+    base::Entity::find()
+        .order_by_asc(base::Column::Id)
+        .one(db)
+        .await
+}
+
+/// Creates the base point from the config if there is none yet, returning whether it did
+///
+/// # Errors
+/// Returns [`AppError::Invalid`] if the configured base point is not valid.
+pub async fn seed(db: &DatabaseConnection, form: Option<BaseForm>) -> Result<bool, AppError> {
+    let Some(form) = form else {
+        return Ok(false);
+    };
+    if base::Entity::find().count(db).await? > 0 {
+        return Ok(false);
+    }
+    form.validate().map_err(AppError::Invalid)?;
+    let mut model = base::ActiveModel {
+        ..Default::default()
+    };
+    form.apply(&mut model);
+    model.insert(db).await?;
+    Ok(true)
+}
+
+/// Every base point with the universities its alumni went to, only those of a class if given
+///
+/// # Errors
+/// Returns the error if the database cannot be queried.
+pub async fn with_destinations(
+    db: &DatabaseConnection,
+    class_of: Option<i32>,
+) -> Result<Vec<base::WithDestinations>, DbErr> {
+    let bases = base::Entity::find()
+        .order_by_asc(base::Column::Id)
+        .all(db)
+        .await?;
+    let default_id = bases.first().map(|each| each.id);
+    let mut query = profile::Entity::find();
+    if let Some(class_of) = class_of {
+        query = query.filter(profile::Column::ClassOf.eq(class_of));
+    }
+    let links: Vec<(Option<i32>, i32)> = query
+        .select_only()
+        .column(profile::Column::BaseId)
+        .column(profile::Column::UniversityId)
+        .distinct()
+        .into_tuple()
+        .all(db)
+        .await?;
+    let mut destinations: BTreeMap<i32, Vec<i32>> = BTreeMap::new();
+    for (base_id, university_id) in links {
+        if let Some(base_id) = base_id.or(default_id) {
+            destinations.entry(base_id).or_default().push(university_id);
+        }
+    }
+    Ok(bases
+        .into_iter()
+        .map(|base| {
+            let mut universities = destinations.remove(&base.id).unwrap_or_default();
+            universities.sort_unstable();
+            universities.dedup();
+            base::WithDestinations { base, universities }
+        })
+        .collect())
+}
+
+/// Matches profiles that set out from the given base point
+///
+/// # Errors
+/// Returns the error if the database cannot be queried.
+pub async fn cohort(db: &DatabaseConnection, base_id: i32) -> Result<Condition, DbErr> {
+    let mut condition = Condition::any().add(profile::Column::BaseId.eq(base_id));
+    if default_base(db)
+        .await?
+        .is_some_and(|found| found.id == base_id)
+    {
+        condition = condition.add(profile::Column::BaseId.is_null());
+    }
+    Ok(condition)
+}
+
+/// Deletes a base point, moving its profiles to `reassign_to` if given, returning how many moved
+///
+/// Profiles that name no base point count as setting out from the default one, so deleting it
+/// moves them too.
+///
+/// # Errors
+/// Returns [`AppError::NotFound`] if there is no such base point, [`AppError::Conflict`] if
+/// profiles still set out from it and `reassign_to` is not given, and [`AppError::Invalid`] if
+/// `reassign_to` is not another existing base point.
+pub async fn delete_base(
+    db: &DatabaseConnection,
+    base_id: i32,
+    reassign_to: Option<i32>,
+) -> Result<u64, AppError> {
+    let condition = cohort(db, base_id).await?;
+    let txn = db.begin().await?;
+    base::Entity::find_by_id(base_id)
+        .one(&txn)
+        .await?
+        .ok_or(AppError::NotFound("No such base point"))?;
+    let profiles = profile::Entity::find()
+        .filter(condition.clone())
+        .count(&txn)
+        .await?;
+    let moved = match reassign_to {
+        _ if profiles == 0 => 0,
+        None => {
+            return Err(AppError::Conflict(format!(
+                "{profiles} profiles still set out from this base point, \
+                 move them or name a base point to reassign them to"
+            )))
+        }
+        Some(target) => {
+            let exists = base::Entity::find_by_id(target).one(&txn).await?.is_some();
+            if target == base_id || !exists {
+                return Err(AppError::Invalid(vec![FieldError::new(
+                    "reassign_to",
+                    "must be another existing base point",
+                )]));
+            }
+            profile::Entity::update_many()
+                .col_expr(profile::Column::BaseId, Expr::value(Some(target)))
+                .filter(condition)
+                .exec(&txn)
+                .await?
+                .rows_affected
+        }
+    };
+    base::Entity::delete_by_id(base_id).exec(&txn).await?;
+    txn.commit().await?;
+    Ok(moved)
+}
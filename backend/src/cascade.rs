@@ -0,0 +1,55 @@
+//! What happens to profiles when their university is deleted
+//!
+//! The database refuses to delete a university while profiles still refer to it, so no profile is
+//! ever left pointing nowhere. Whether deleting such a university fails or takes its profiles
+//! along is up to the `on_university_delete` setting.
+
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, ModelTrait, PaginatorTrait, QueryFilter,
+    TransactionTrait,
+};
+use serde::Deserialize;
+
+use crate::error::AppError;
+use entity::{profile, university};
+
+/// Behaviour of deleting a university that still has profiles
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnUniversityDelete {
+    /// Refuse, the profiles have to be moved or deleted first
+    #[default]
+    Restrict,
+    /// Delete the profiles along with the university
+    Cascade,
+}
+
+/// Deletes a university according to the rule, returning how many profiles went with it
+///
+/// # Errors
+/// Returns [`AppError::NotFound`] if there is no such university, and [`AppError::Conflict`] if it
+/// still has profiles and the rule is [`OnUniversityDelete::Restrict`].
+pub async fn delete_university(
+    db: &DatabaseConnection,
+    uni_id: i32,
+    rule: OnUniversityDelete,
+) -> Result<u64, AppError> {
+    let txn = db.begin().await?;
+    let found = university::Entity::find_by_id(uni_id)
+        .one(&txn)
+        .await?
+        .ok_or(AppError::NotFound("No such university"))?;
+    let profiles = found.find_related(profile::Entity).count(&txn).await?;
+    if profiles > 0 && rule == OnUniversityDelete::Restrict {
+        return Err(AppError::Conflict(format!(
+            "{profiles} profiles still go to this university, move or delete them first"
+        )));
+    }
+    profile::Entity::delete_many()
+        .filter(profile::Column::UniversityId.eq(uni_id))
+        .exec(&txn)
+        .await?;
+    found.delete(&txn).await?;
+    txn.commit().await?;
+    Ok(profiles)
+}
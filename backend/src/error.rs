@@ -0,0 +1,165 @@
+//! The error type shared by the HTTP handlers and the command line
+//!
+//! Over HTTP every error is answered with a JSON body like
+//! ```json
+//! {"code": "not_found", "message": "No such university"}
+//! ```
+//! Validation failures also list every problem found in `errors`. Errors on our side are logged
+//! in full, while the client only learns that something went wrong.
+
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use sea_orm::DbErr;
+use serde::Serialize;
+use std::fmt::Display;
+
+use crate::forms::FieldError;
+
+/// Errors that may happen in the App
+#[derive(Debug)]
+pub enum AppError {
+    /// The configuration file is missing or malformed
+    Config(config::ConfigError),
+    /// The database cannot be reached or refused a query
+    Database(DbErr),
+    /// A file cannot be read or written
+    Io(std::io::Error),
+    /// App cannot listen on the specified address
+    Listen(std::io::Error),
+    /// Something else went wrong on our side
+    Internal(String),
+    /// The input is unusable as a whole, e.g. a file of an unsupported kind
+    BadRequest(String),
+    /// Some fields of the input are invalid
+    Invalid(Vec<FieldError>),
+    /// The requested entry does not exist
+    NotFound(&'static str),
+    /// Logging in is required, or the credentials are wrong
+    Unauthorized(&'static str),
+    /// The account may not do this
+    Forbidden,
+    /// The change would break other entries, e.g. deleting a university with profiles
+    Conflict(String),
+}
+
+impl AppError {
+    /// Short machine readable name of the error, sent as `code`
+    #[must_use]
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Config(_)
+            | AppError::Database(_)
+            | AppError::Io(_)
+            | AppError::Listen(_)
+            | AppError::Internal(_) => "internal",
+            AppError::BadRequest(_) => "bad_request",
+            AppError::Invalid(_) => "invalid",
+            AppError::NotFound(_) => "not_found",
+            AppError::Unauthorized(_) => "unauthorized",
+            AppError::Forbidden => "forbidden",
+            AppError::Conflict(_) => "conflict",
+        }
+    }
+
+    /// Whether the error is our fault rather than the client's
+    fn is_internal(&self) -> bool {
+        self.status_code().is_server_error()
+    }
+}
+
+impl std::error::Error for AppError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AppError::Config(err) => Some(err),
+            AppError::Database(err) => Some(err),
+            AppError::Io(err) | AppError::Listen(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppError::Config(err) => write!(f, "Cannot load config: {err}"),
+            AppError::Database(err) => write!(f, "Database error: {err}"),
+            AppError::Io(err) => write!(f, "I/O error: {err}"),
+            AppError::Listen(err) => write!(f, "Cannot listen on the specified address: {err}"),
+            AppError::Internal(message)
+            | AppError::BadRequest(message)
+            | AppError::Conflict(message) => write!(f, "{message}"),
+            AppError::Invalid(errors) => {
+                write!(f, "Invalid input")?;
+                for error in errors {
+                    write!(f, "\n{}: {}", error.field, error.message)?;
+                }
+                Ok(())
+            }
+            AppError::NotFound(message) | AppError::Unauthorized(message) => {
+                write!(f, "{message}")
+            }
+            AppError::Forbidden => write!(f, "This account may not change that"),
+        }
+    }
+}
+
+impl From<DbErr> for AppError {
+    fn from(err: DbErr) -> Self {
+        AppError::Database(err)
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(err: std::io::Error) -> Self {
+        AppError::Io(err)
+    }
+}
+
+impl From<config::ConfigError> for AppError {
+    fn from(err: config::ConfigError) -> Self {
+        AppError::Config(err)
+    }
+}
+
+/// Body of every error response
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    code: &'static str,
+    message: String,
+    #[serde(skip_serializing_if = "<[FieldError]>::is_empty")]
+    errors: &'a [FieldError],
+}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::Config(_)
+            | AppError::Database(_)
+            | AppError::Io(_)
+            | AppError::Listen(_)
+            | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Invalid(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden => StatusCode::FORBIDDEN,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let (message, errors) = match self {
+            AppError::Invalid(errors) => ("Invalid input".to_string(), errors.as_slice()),
+            _ if self.is_internal() => {
+                log::error!("{self}");
+                ("Internal server error".to_string(), [].as_slice())
+            }
+            _ => (self.to_string(), [].as_slice()),
+        };
+        HttpResponse::build(self.status_code()).json(ErrorBody {
+            code: self.code(),
+            message,
+            errors,
+        })
+    }
+}
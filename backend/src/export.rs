@@ -0,0 +1,211 @@
+//! Dumps the whole dataset for backups, spreadsheets and GIS tools
+//!
+//! JSON exports use the same layout as `alumnimap import`, so they can be imported again.
+
+use sea_orm::{DatabaseConnection, DbErr, EntityTrait};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::error::Error;
+
+use crate::error::AppError;
+use entity::{base, profile, university};
+
+/// Supported export formats
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// Universities, base points and profiles, importable again
+    #[default]
+    Json,
+    /// One row per profile, joined with its university
+    Csv,
+    /// A `FeatureCollection` with one point per university
+    #[value(name = "geojson")]
+    GeoJson,
+}
+
+impl Format {
+    #[must_use]
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Format::Json => "application/json",
+            Format::Csv => "text/csv; charset=utf-8",
+            Format::GeoJson => "application/geo+json",
+        }
+    }
+
+    #[must_use]
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Csv => "csv",
+            Format::GeoJson => "geojson",
+        }
+    }
+}
+
+/// Everything in the database, with each profile joined to its university
+pub struct Dataset {
+    pub universities: Vec<university::Model>,
+    pub bases: Vec<base::Model>,
+    /// The university is missing if the profile refers to one that no longer exists
+    pub profiles: Vec<(profile::Model, Option<university::Model>)>,
+}
+
+impl Dataset {
+    /// Loads everything from the database
+    ///
+    /// # Errors
+    /// Returns the error if the database cannot be queried.
+    pub async fn load(db: &DatabaseConnection) -> Result<Self, DbErr> {
+        let universities = university::Entity::find().all(db).await?;
+        let bases = base::Entity::find().all(db).await?;
+        let profiles = profile::Entity::find()
+            .find_also_related(university::Entity)
+            .all(db)
+            .await?;
+        Ok(Self {
+            universities,
+            bases,
+            profiles,
+        })
+    }
+
+    /// Renders the dataset in the given format
+    ///
+    /// # Errors
+    /// Fails only if serialisation does, which should not happen with these types.
+    pub fn render(&self, format: Format) -> Result<String, AppError> {
+        match format {
+            Format::Json => self.to_json(),
+            Format::Csv => self.to_csv(),
+            Format::GeoJson => self.to_geojson(),
+        }
+        .map_err(|err| AppError::Internal(format!("Cannot render export: {err}")))
+    }
+
+    /// Title of the base point the profile names, if any
+    fn base_title(&self, profile: &profile::Model) -> Option<&str> {
+        let base_id = profile.base_id?;
+        self.bases
+            .iter()
+            .find(|each| each.id == base_id)
+            .map(|each| each.title.as_str())
+    }
+
+    fn to_json(&self) -> Result<String, Box<dyn Error>> {
+        let profiles: Vec<_> = self
+            .profiles
+            .iter()
+            .map(|(profile, university)| JsonProfile {
+                profile,
+                university: university.as_ref().map(|each| each.title.as_str()),
+                base: self.base_title(profile),
+            })
+            .collect();
+        Ok(serde_json::to_string_pretty(&json!({
+            "universities": self.universities,
+            "bases": self.bases,
+            "profiles": profiles,
+        }))?)
+    }
+
+    fn to_csv(&self) -> Result<String, Box<dyn Error>> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        for (profile, university) in &self.profiles {
+            writer.serialize(CsvRow {
+                id: profile.id,
+                name_primary: &profile.name_primary,
+                name_supplementary: profile.name_supplementary.as_deref(),
+                avatar: &profile.avatar,
+                class_of: profile.class_of,
+                university: university.as_ref().map(|each| each.title.as_str()),
+                longitude: university.as_ref().map(|each| each.longitude),
+                latitude: university.as_ref().map(|each| each.latitude),
+                base: self.base_title(profile),
+                major: profile.major.as_deref(),
+                bio: profile.bio.as_deref(),
+                email: profile.email.as_deref(),
+                qq: profile.qq.as_deref(),
+                wechat: profile.wechat.as_deref(),
+                matrix: profile.matrix.as_deref(),
+                contact_visibility: profile.contact_visibility,
+            })?;
+        }
+        Ok(String::from_utf8(writer.into_inner()?)?)
+    }
+
+    fn to_geojson(&self) -> Result<String, Box<dyn Error>> {
+        let features: Vec<_> = self
+            .universities
+            .iter()
+            .map(|each| {
+                let alumni: Vec<_> = self
+                    .profiles
+                    .iter()
+                    .filter(|(profile, _)| profile.university_id == each.id)
+                    .map(|(profile, _)| {
+                        json!({
+                            "id": profile.id,
+                            "name_primary": profile.name_primary,
+                            "name_supplementary": profile.name_supplementary,
+                            "class_of": profile.class_of,
+                            "major": profile.major,
+                        })
+                    })
+                    .collect();
+                json!({
+                    "type": "Feature",
+                    "geometry": {
+                        "type": "Point",
+                        "coordinates": [each.longitude, each.latitude],
+                    },
+                    "properties": {
+                        "id": each.id,
+                        "title": each.title,
+                        "icon": each.icon,
+                        "colour": each.colour,
+                        "alumni_count": alumni.len(),
+                        "alumni": alumni,
+                    },
+                })
+            })
+            .collect();
+        Ok(serde_json::to_string_pretty(&json!({
+            "type": "FeatureCollection",
+            "features": features,
+        }))?)
+    }
+}
+
+/// A profile with its university and base point referred to by title, as `alumnimap import`
+/// expects
+#[derive(Serialize)]
+struct JsonProfile<'a> {
+    #[serde(flatten)]
+    profile: &'a profile::Model,
+    university: Option<&'a str>,
+    /// Missing for the default base point
+    base: Option<&'a str>,
+}
+
+/// A profile flattened into a single CSV row
+#[derive(Serialize)]
+struct CsvRow<'a> {
+    id: i32,
+    name_primary: &'a str,
+    name_supplementary: Option<&'a str>,
+    avatar: &'a str,
+    class_of: i32,
+    university: Option<&'a str>,
+    longitude: Option<f32>,
+    latitude: Option<f32>,
+    base: Option<&'a str>,
+    major: Option<&'a str>,
+    bio: Option<&'a str>,
+    email: Option<&'a str>,
+    qq: Option<&'a str>,
+    wechat: Option<&'a str>,
+    matrix: Option<&'a str>,
+    contact_visibility: profile::Visibility,
+}
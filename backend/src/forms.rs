@@ -0,0 +1,478 @@
+//! Request bodies accepted by the write endpoints
+//!
+//! Each entity has a full form, used when creating or replacing a record, and a patch, whose
+//! fields are all optional and only overwrite what is present.
+
+use sea_orm::ActiveValue::Set;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::path::Path;
+
+use crate::tiles;
+use entity::base;
+use entity::map::MapConfig;
+use entity::profile::{self, Visibility};
+use entity::university;
+
+/// A single problem found while validating a form
+#[derive(Debug, Serialize)]
+pub struct FieldError {
+    pub field: &'static str,
+    pub message: String,
+}
+
+impl FieldError {
+    #[must_use]
+    pub fn new(field: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            field,
+            message: message.into(),
+        }
+    }
+}
+
+/// Credentials sent to log in
+#[derive(Debug, Deserialize)]
+pub struct LoginForm {
+    pub username: String,
+    pub password: String,
+}
+
+/// Full description of a university
+#[derive(Debug, Deserialize)]
+pub struct UniversityForm {
+    pub title: String,
+    pub icon: String,
+    pub colour: String,
+    pub longitude: f32,
+    pub latitude: f32,
+}
+
+impl UniversityForm {
+    /// Checks every field, collecting all problems instead of stopping at the first one
+    ///
+    /// # Errors
+    /// Returns every [`FieldError`] found.
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        check_title(&self.title, &mut errors);
+        check_colour(&self.colour, &mut errors);
+        check_longitude(self.longitude, &mut errors);
+        check_latitude(self.latitude, &mut errors);
+        finish(errors)
+    }
+
+    /// Writes every field into the given [`university::ActiveModel`]
+    pub fn apply(self, model: &mut university::ActiveModel) {
+        model.title = Set(self.title);
+        model.icon = Set(self.icon);
+        model.colour = Set(self.colour);
+        model.longitude = Set(self.longitude);
+        model.latitude = Set(self.latitude);
+    }
+}
+
+/// Partial update of a university
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct UniversityPatch {
+    pub title: Option<String>,
+    pub icon: Option<String>,
+    pub colour: Option<String>,
+    pub longitude: Option<f32>,
+    pub latitude: Option<f32>,
+}
+
+impl UniversityPatch {
+    /// Checks every present field
+    ///
+    /// # Errors
+    /// Returns every [`FieldError`] found.
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        if let Some(title) = &self.title {
+            check_title(title, &mut errors);
+        }
+        if let Some(colour) = &self.colour {
+            check_colour(colour, &mut errors);
+        }
+        if let Some(longitude) = self.longitude {
+            check_longitude(longitude, &mut errors);
+        }
+        if let Some(latitude) = self.latitude {
+            check_latitude(latitude, &mut errors);
+        }
+        finish(errors)
+    }
+
+    /// Writes present fields into the given [`university::ActiveModel`]
+    pub fn apply(self, model: &mut university::ActiveModel) {
+        if let Some(title) = self.title {
+            model.title = Set(title);
+        }
+        if let Some(icon) = self.icon {
+            model.icon = Set(icon);
+        }
+        if let Some(colour) = self.colour {
+            model.colour = Set(colour);
+        }
+        if let Some(longitude) = self.longitude {
+            model.longitude = Set(longitude);
+        }
+        if let Some(latitude) = self.latitude {
+            model.latitude = Set(latitude);
+        }
+    }
+}
+
+/// Full description of a base point, also read from the `[base]` section of the config
+#[derive(Debug, Deserialize)]
+pub struct BaseForm {
+    pub title: String,
+    pub colour: String,
+    pub longitude: f32,
+    pub latitude: f32,
+}
+
+impl BaseForm {
+    /// Checks every field, collecting all problems instead of stopping at the first one
+    ///
+    /// # Errors
+    /// Returns every [`FieldError`] found.
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        check_title(&self.title, &mut errors);
+        check_colour(&self.colour, &mut errors);
+        check_longitude(self.longitude, &mut errors);
+        check_latitude(self.latitude, &mut errors);
+        finish(errors)
+    }
+
+    /// Writes every field into the given [`base::ActiveModel`]
+    pub fn apply(self, model: &mut base::ActiveModel) {
+        model.title = Set(self.title);
+        model.colour = Set(self.colour);
+        model.longitude = Set(self.longitude);
+        model.latitude = Set(self.latitude);
+    }
+}
+
+/// Partial update of a base point
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct BasePatch {
+    pub title: Option<String>,
+    pub colour: Option<String>,
+    pub longitude: Option<f32>,
+    pub latitude: Option<f32>,
+}
+
+impl BasePatch {
+    /// Checks every present field
+    ///
+    /// # Errors
+    /// Returns every [`FieldError`] found.
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        if let Some(title) = &self.title {
+            check_title(title, &mut errors);
+        }
+        if let Some(colour) = &self.colour {
+            check_colour(colour, &mut errors);
+        }
+        if let Some(longitude) = self.longitude {
+            check_longitude(longitude, &mut errors);
+        }
+        if let Some(latitude) = self.latitude {
+            check_latitude(latitude, &mut errors);
+        }
+        finish(errors)
+    }
+
+    /// Writes present fields into the given [`base::ActiveModel`]
+    pub fn apply(self, model: &mut base::ActiveModel) {
+        if let Some(title) = self.title {
+            model.title = Set(title);
+        }
+        if let Some(colour) = self.colour {
+            model.colour = Set(colour);
+        }
+        if let Some(longitude) = self.longitude {
+            model.longitude = Set(longitude);
+        }
+        if let Some(latitude) = self.latitude {
+            model.latitude = Set(latitude);
+        }
+    }
+}
+
+/// Full description of a profile
+#[derive(Debug, Deserialize)]
+pub struct ProfileForm {
+    pub name_primary: String,
+    #[serde(default)]
+    pub name_supplementary: Option<String>,
+    pub avatar: String,
+    pub class_of: i32,
+    pub university_id: i32,
+    /// The default base point if missing
+    #[serde(default)]
+    pub base_id: Option<i32>,
+    #[serde(default)]
+    pub major: Option<String>,
+    #[serde(default)]
+    pub bio: Option<String>,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub qq: Option<String>,
+    #[serde(default)]
+    pub wechat: Option<String>,
+    #[serde(default)]
+    pub matrix: Option<String>,
+    #[serde(default)]
+    pub contact_visibility: Visibility,
+}
+
+impl ProfileForm {
+    /// Checks every field, collecting all problems instead of stopping at the first one
+    ///
+    /// Whether `university_id` and `base_id` refer to existing entries is not checked here, since
+    /// that needs the database.
+    ///
+    /// # Errors
+    /// Returns every [`FieldError`] found.
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        check_name(&self.name_primary, &mut errors);
+        check_class_of(self.class_of, &mut errors);
+        if let Some(email) = &self.email {
+            check_email(email, &mut errors);
+        }
+        finish(errors)
+    }
+
+    /// Writes every field into the given [`profile::ActiveModel`]
+    pub fn apply(self, model: &mut profile::ActiveModel) {
+        model.name_primary = Set(self.name_primary);
+        model.name_supplementary = Set(self.name_supplementary);
+        model.avatar = Set(self.avatar);
+        model.class_of = Set(self.class_of);
+        model.university_id = Set(self.university_id);
+        model.base_id = Set(self.base_id);
+        model.major = Set(self.major);
+        model.bio = Set(self.bio);
+        model.email = Set(self.email);
+        model.qq = Set(self.qq);
+        model.wechat = Set(self.wechat);
+        model.matrix = Set(self.matrix);
+        model.contact_visibility = Set(self.contact_visibility);
+    }
+}
+
+/// Partial update of a profile
+///
+/// Nullable columns are wrapped twice so that an explicit `null` clears the value, while an absent
+/// field leaves it untouched.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ProfilePatch {
+    pub name_primary: Option<String>,
+    #[serde(deserialize_with = "nullable")]
+    pub name_supplementary: Option<Option<String>>,
+    pub avatar: Option<String>,
+    pub class_of: Option<i32>,
+    pub university_id: Option<i32>,
+    /// `null` moves the profile to the default base point
+    #[serde(deserialize_with = "nullable")]
+    pub base_id: Option<Option<i32>>,
+    #[serde(deserialize_with = "nullable")]
+    pub major: Option<Option<String>>,
+    #[serde(deserialize_with = "nullable")]
+    pub bio: Option<Option<String>>,
+    #[serde(deserialize_with = "nullable")]
+    pub email: Option<Option<String>>,
+    #[serde(deserialize_with = "nullable")]
+    pub qq: Option<Option<String>>,
+    #[serde(deserialize_with = "nullable")]
+    pub wechat: Option<Option<String>>,
+    #[serde(deserialize_with = "nullable")]
+    pub matrix: Option<Option<String>>,
+    pub contact_visibility: Option<Visibility>,
+}
+
+impl ProfilePatch {
+    /// Checks every present field
+    ///
+    /// # Errors
+    /// Returns every [`FieldError`] found.
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        if let Some(name) = &self.name_primary {
+            check_name(name, &mut errors);
+        }
+        if let Some(class_of) = self.class_of {
+            check_class_of(class_of, &mut errors);
+        }
+        if let Some(Some(email)) = &self.email {
+            check_email(email, &mut errors);
+        }
+        finish(errors)
+    }
+
+    /// Writes present fields into the given [`profile::ActiveModel`]
+    pub fn apply(self, model: &mut profile::ActiveModel) {
+        if let Some(name_primary) = self.name_primary {
+            model.name_primary = Set(name_primary);
+        }
+        if let Some(name_supplementary) = self.name_supplementary {
+            model.name_supplementary = Set(name_supplementary);
+        }
+        if let Some(avatar) = self.avatar {
+            model.avatar = Set(avatar);
+        }
+        if let Some(class_of) = self.class_of {
+            model.class_of = Set(class_of);
+        }
+        if let Some(university_id) = self.university_id {
+            model.university_id = Set(university_id);
+        }
+        if let Some(base_id) = self.base_id {
+            model.base_id = Set(base_id);
+        }
+        if let Some(major) = self.major {
+            model.major = Set(major);
+        }
+        if let Some(bio) = self.bio {
+            model.bio = Set(bio);
+        }
+        if let Some(email) = self.email {
+            model.email = Set(email);
+        }
+        if let Some(qq) = self.qq {
+            model.qq = Set(qq);
+        }
+        if let Some(wechat) = self.wechat {
+            model.wechat = Set(wechat);
+        }
+        if let Some(matrix) = self.matrix {
+            model.matrix = Set(matrix);
+        }
+        if let Some(contact_visibility) = self.contact_visibility {
+            model.contact_visibility = Set(contact_visibility);
+        }
+    }
+}
+
+/// Keeps an explicit `null` apart from a missing field
+#[allow(clippy::option_option)]
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Checks the `[map]` section of the config
+///
+/// # Errors
+/// Returns every [`FieldError`] found.
+pub fn validate_map(map: &MapConfig) -> Result<(), Vec<FieldError>> {
+    let mut errors = Vec::new();
+    if let Some(image) = &map.image {
+        let path = Path::new(image);
+        if path
+            .extension()
+            .is_none_or(|ext| ext != "png" && ext != "svg")
+        {
+            errors.push(FieldError::new("map.image", "must be a PNG or SVG picture"));
+        }
+        if path.is_absolute() || image.contains("..") {
+            errors.push(FieldError::new(
+                "map.image",
+                "must be inside the assets directory",
+            ));
+        }
+    }
+    if map.tile_zoom.is_some_and(|zoom| zoom > tiles::MAX_ZOOM) {
+        errors.push(FieldError::new(
+            "map.tile_zoom",
+            format!("must be at most {}", tiles::MAX_ZOOM),
+        ));
+    }
+    if !(-0.5..=0.5).contains(&map.line_curvature) {
+        errors.push(FieldError::new(
+            "map.line_curvature",
+            "must be between -0.5 and 0.5",
+        ));
+    }
+    for (field, scale) in [
+        ("map.longitude_scale", map.longitude_scale),
+        ("map.latitude_scale", map.latitude_scale),
+    ] {
+        if !scale.is_normal() {
+            errors.push(FieldError::new(field, "must not be zero"));
+        }
+    }
+    finish(errors)
+}
+
+fn finish(errors: Vec<FieldError>) -> Result<(), Vec<FieldError>> {
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn check_title(title: &str, errors: &mut Vec<FieldError>) {
+    if title.trim().is_empty() {
+        errors.push(FieldError::new("title", "must not be empty"));
+    }
+}
+
+fn check_name(name: &str, errors: &mut Vec<FieldError>) {
+    if name.trim().is_empty() {
+        errors.push(FieldError::new("name_primary", "must not be empty"));
+    }
+}
+
+/// Colours are parsed by `Color32::from_hex` in the frontend, which wants `#RRGGBB` or `#RRGGBBAA`
+fn check_colour(colour: &str, errors: &mut Vec<FieldError>) {
+    let valid = colour.strip_prefix('#').is_some_and(|hex| {
+        (hex.len() == 6 || hex.len() == 8) && hex.chars().all(|c| c.is_ascii_hexdigit())
+    });
+    if !valid {
+        errors.push(FieldError::new(
+            "colour",
+            "must be a hex colour like #RRGGBB or #RRGGBBAA",
+        ));
+    }
+}
+
+fn check_longitude(longitude: f32, errors: &mut Vec<FieldError>) {
+    if !(-180.0..=180.0).contains(&longitude) {
+        errors.push(FieldError::new("longitude", "must be between -180 and 180"));
+    }
+}
+
+fn check_latitude(latitude: f32, errors: &mut Vec<FieldError>) {
+    if !(-90.0..=90.0).contains(&latitude) {
+        errors.push(FieldError::new("latitude", "must be between -90 and 90"));
+    }
+}
+
+fn check_class_of(class_of: i32, errors: &mut Vec<FieldError>) {
+    if !(1900..=2200).contains(&class_of) {
+        errors.push(FieldError::new(
+            "class_of",
+            "must be a year between 1900 and 2200",
+        ));
+    }
+}
+
+fn check_email(email: &str, errors: &mut Vec<FieldError>) {
+    if !email.contains('@') {
+        errors.push(FieldError::new("email", "must be an email address"));
+    }
+}
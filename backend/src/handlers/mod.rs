@@ -0,0 +1,585 @@
+mod upload;
+pub mod v2;
+
+pub use upload::{upload_avatar, upload_icon};
+
+use actix_files::NamedFile;
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
+use actix_web::{delete, get, patch, post, put, web, HttpRequest, HttpResponse};
+use rust_embed::Embed;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect,
+};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::auth;
+use crate::bases;
+use crate::cascade;
+use crate::error::AppError;
+use crate::export::{Dataset, Format};
+use crate::forms::{
+    FieldError, LoginForm, ProfileForm, ProfilePatch, UniversityForm, UniversityPatch,
+};
+use crate::search;
+use crate::server::AppState;
+use crate::tiles;
+use entity::account::{self, Role};
+use entity::{base, profile, session, university};
+
+#[derive(Embed)]
+#[folder = "../frontend/dist"]
+struct Dist;
+
+#[get("/{path:.*}")]
+pub async fn index(path: web::Path<String>) -> HttpResponse {
+    let mut path = path.into_inner();
+    if path.is_empty() {
+        path = "index.html".to_string();
+    }
+    match Dist::get(&path) {
+        Some(content) => HttpResponse::Ok()
+            .content_type(mime_guess::from_path(path).first_or_octet_stream().as_ref())
+            .body(content.data.into_owned()),
+        None => HttpResponse::NotFound().body("404 Not Found"),
+    }
+}
+
+#[get("/static/{filename:.*}")]
+pub async fn png(req: HttpRequest, state: web::Data<AppState>) -> Result<NamedFile, AppError> {
+    let mut path = PathBuf::from(req.match_info().query("filename"));
+    if path
+        .extension()
+        .is_none_or(|ext| ext != "png" && ext != "svg")
+    {
+        return Err(AppError::NotFound("This site serves only images"));
+    }
+    path = PathBuf::from(state.assets_root.clone()).join(path);
+    NamedFile::open(path).map_err(|_| AppError::NotFound("No such file"))
+}
+
+/// A tile cut by `alumnimap tiles`
+#[get("/tiles/{zoom}/{x}/{y}.png")]
+pub async fn tile(
+    state: web::Data<AppState>,
+    path: web::Path<(u32, u32, u32)>,
+) -> Result<NamedFile, AppError> {
+    let (zoom, x, y) = path.into_inner();
+    let path = tiles::tile_path(Path::new(&state.assets_root), zoom, x, y);
+    NamedFile::open(path).map_err(|_| AppError::NotFound("No such tile"))
+}
+
+#[get("/ping")]
+pub async fn ping() -> HttpResponse {
+    HttpResponse::Ok().body("Up and running")
+}
+
+/// Narrows a list down to a single graduating class
+#[derive(Deserialize)]
+pub struct ClassQuery {
+    class_of: Option<i32>,
+}
+
+/// Narrows the profiles of a university down to a base point and a graduating class
+#[derive(Default, Deserialize)]
+pub struct ProfileFilter {
+    base_id: Option<i32>,
+    class_of: Option<i32>,
+}
+
+/// With `class_of`, only universities with alumni from that year
+#[get("/universities")]
+pub async fn universities(
+    state: web::Data<AppState>,
+    query: web::Query<ClassQuery>,
+) -> Result<HttpResponse, AppError> {
+    let list = universities_of_class(&state.db, query.class_of).await?;
+    Ok(HttpResponse::Ok().json(serde_json::to_string(&list).unwrap_or_default()))
+}
+
+/// The default base point, shaped like a university with id -1 as old clients expect
+#[get("/base")]
+pub async fn get_base(state: web::Data<AppState>) -> Result<HttpResponse, AppError> {
+    let found = find_default_base(&state.db).await?;
+    let legacy = university::Model {
+        id: -1,
+        title: found.title,
+        icon: String::new(),
+        colour: found.colour,
+        longitude: found.longitude,
+        latitude: found.latitude,
+    };
+    Ok(HttpResponse::Ok().json(serde_json::to_string(&legacy).unwrap_or_default()))
+}
+
+/// Every base point with the universities its alumni went to, only those of `class_of` if given
+#[get("/bases")]
+pub async fn list_bases(
+    state: web::Data<AppState>,
+    query: web::Query<ClassQuery>,
+) -> Result<HttpResponse, AppError> {
+    let list = bases::with_destinations(&state.db, query.class_of).await?;
+    Ok(HttpResponse::Ok().json(list))
+}
+
+/// Every university with how many alumni went there, only those with alumni of `class_of` if given
+#[get("/alumni_counts")]
+pub async fn alumni_counts(
+    state: web::Data<AppState>,
+    query: web::Query<ClassQuery>,
+) -> Result<HttpResponse, AppError> {
+    let list = universities_with_alumni(&state.db, query.class_of).await?;
+    Ok(HttpResponse::Ok().json(list))
+}
+
+/// The picture under the map and how to line points up with it
+#[get("/map")]
+pub async fn map(state: web::Data<AppState>) -> HttpResponse {
+    HttpResponse::Ok().json(&state.map)
+}
+
+/// Every graduating class with how many alumni it has, oldest first
+#[get("/years")]
+pub async fn years(state: web::Data<AppState>) -> Result<HttpResponse, AppError> {
+    let list: Vec<profile::ClassSize> = profile::Entity::find()
+        .select_only()
+        .column(profile::Column::ClassOf)
+        .column_as(profile::Column::Id.count(), "alumni")
+        .group_by(profile::Column::ClassOf)
+        .order_by_asc(profile::Column::ClassOf)
+        .into_tuple::<(i32, i64)>()
+        .all(&state.db)
+        .await?
+        .into_iter()
+        .map(|(class_of, alumni)| profile::ClassSize { class_of, alumni })
+        .collect();
+    Ok(HttpResponse::Ok().json(list))
+}
+
+#[get("/universities/{uni_id}")]
+pub async fn get_uni_name(
+    state: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let found = find_university(&state.db, path.into_inner()).await?;
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; charset=utf-8")
+        .body(found.title))
+}
+
+#[get("/profiles/{uni_id}")]
+pub async fn profiles(
+    state: web::Data<AppState>,
+    viewer: Option<web::ReqData<account::Model>>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let list = visible_profiles(
+        &state.db,
+        viewer.as_deref(),
+        path.into_inner(),
+        &ProfileFilter::default(),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(serde_json::to_string(&list).unwrap_or_default()))
+}
+
+#[get("/search/universities/{search_text}")]
+pub async fn search_university(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let list = university::Entity::find()
+        .filter(university::Column::Title.contains(path.into_inner()))
+        .all(&state.db)
+        .await?;
+    Ok(HttpResponse::Ok().json(serde_json::to_string(&list).unwrap_or_default()))
+}
+
+#[derive(Deserialize)]
+pub struct SearchQuery {
+    #[serde(default)]
+    q: String,
+}
+
+/// Universities and profiles matching `q`, each tagged with its `kind`
+#[get("/search")]
+pub async fn search_all(
+    state: web::Data<AppState>,
+    viewer: Option<web::ReqData<account::Model>>,
+    query: web::Query<SearchQuery>,
+) -> Result<HttpResponse, AppError> {
+    let text = query.q.trim();
+    if text.is_empty() {
+        return Err(AppError::Invalid(vec![FieldError::new(
+            "q",
+            "must not be empty",
+        )]));
+    }
+    let hits = search::search(&state.db, viewer.as_deref(), text).await?;
+    Ok(HttpResponse::Ok().json(hits))
+}
+
+#[derive(Deserialize)]
+pub struct ExportQuery {
+    #[serde(default)]
+    format: Format,
+}
+
+/// Dumps everything including contacts, so only admins may do it
+#[get("/export")]
+pub async fn export(
+    state: web::Data<AppState>,
+    account: Option<web::ReqData<account::Model>>,
+    query: web::Query<ExportQuery>,
+) -> Result<HttpResponse, AppError> {
+    match account {
+        Some(account) if account.role == Role::Admin => (),
+        Some(_) => return Err(AppError::Forbidden),
+        None => return Err(AppError::Unauthorized("Login required")),
+    }
+    let format = query.format;
+    let rendered = Dataset::load(&state.db).await?.render(format)?;
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(format!(
+                "alumnimap.{}",
+                format.extension()
+            ))],
+        })
+        .body(rendered))
+}
+
+#[derive(Serialize)]
+struct LoginResult {
+    token: String,
+    expires_at: i64,
+    role: Role,
+}
+
+#[post("/login")]
+pub async fn login(
+    state: web::Data<AppState>,
+    body: web::Json<LoginForm>,
+) -> Result<HttpResponse, AppError> {
+    let form = body.into_inner();
+    let found = account::Entity::find()
+        .filter(account::Column::Username.eq(form.username))
+        .one(&state.db)
+        .await?;
+    let account = auth::check_login(found, &form.password)
+        .ok_or(AppError::Unauthorized("Wrong username or password"))?;
+    auth::delete_expired_sessions(&state.db).await?;
+    let session = auth::start_session(&state.db, account.id).await?;
+    Ok(HttpResponse::Ok()
+        .cookie(auth::session_cookie(&session))
+        .json(LoginResult {
+            token: session.token,
+            expires_at: session.expires_at,
+            role: account.role,
+        }))
+}
+
+#[post("/logout")]
+pub async fn logout(
+    state: web::Data<AppState>,
+    session: web::ReqData<session::Model>,
+) -> Result<HttpResponse, AppError> {
+    session::Entity::delete_by_id(session.into_inner().token)
+        .exec(&state.db)
+        .await?;
+    Ok(HttpResponse::NoContent()
+        .cookie(auth::expired_cookie())
+        .finish())
+}
+
+#[post("/universities")]
+pub async fn create_university(
+    state: web::Data<AppState>,
+    account: web::ReqData<account::Model>,
+    form: web::Json<UniversityForm>,
+) -> Result<HttpResponse, AppError> {
+    if account.role == Role::Member {
+        return Err(AppError::Forbidden);
+    }
+    let form = form.into_inner();
+    form.validate().map_err(AppError::Invalid)?;
+    let mut model = university::ActiveModel {
+        ..Default::default()
+    };
+    form.apply(&mut model);
+    let result = model.insert(&state.db).await?;
+    Ok(HttpResponse::Created().json(result))
+}
+
+#[put("/universities/{uni_id}")]
+pub async fn replace_university(
+    state: web::Data<AppState>,
+    account: web::ReqData<account::Model>,
+    path: web::Path<i32>,
+    form: web::Json<UniversityForm>,
+) -> Result<HttpResponse, AppError> {
+    if account.role != Role::Admin {
+        return Err(AppError::Forbidden);
+    }
+    let form = form.into_inner();
+    form.validate().map_err(AppError::Invalid)?;
+    let mut model: university::ActiveModel =
+        find_university(&state.db, path.into_inner()).await?.into();
+    form.apply(&mut model);
+    let result = model.update(&state.db).await?;
+    Ok(HttpResponse::Ok().json(result))
+}
+
+#[patch("/universities/{uni_id}")]
+pub async fn update_university(
+    state: web::Data<AppState>,
+    account: web::ReqData<account::Model>,
+    path: web::Path<i32>,
+    body: web::Json<UniversityPatch>,
+) -> Result<HttpResponse, AppError> {
+    if account.role != Role::Admin {
+        return Err(AppError::Forbidden);
+    }
+    let changes = body.into_inner();
+    changes.validate().map_err(AppError::Invalid)?;
+    let mut model: university::ActiveModel =
+        find_university(&state.db, path.into_inner()).await?.into();
+    changes.apply(&mut model);
+    let result = model.update(&state.db).await?;
+    Ok(HttpResponse::Ok().json(result))
+}
+
+#[delete("/universities/{uni_id}")]
+pub async fn delete_university(
+    state: web::Data<AppState>,
+    account: web::ReqData<account::Model>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    if account.role != Role::Admin {
+        return Err(AppError::Forbidden);
+    }
+    cascade::delete_university(&state.db, path.into_inner(), state.on_university_delete).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[post("/profiles")]
+pub async fn create_profile(
+    state: web::Data<AppState>,
+    account: web::ReqData<account::Model>,
+    form: web::Json<ProfileForm>,
+) -> Result<HttpResponse, AppError> {
+    let form = form.into_inner();
+    if !auth::can_edit_class(&account, form.class_of) {
+        return Err(AppError::Forbidden);
+    }
+    form.validate().map_err(AppError::Invalid)?;
+    check_university(&state.db, form.university_id).await?;
+    check_base(&state.db, form.base_id).await?;
+    let mut model = profile::ActiveModel {
+        ..Default::default()
+    };
+    form.apply(&mut model);
+    let result = model.insert(&state.db).await?;
+    Ok(HttpResponse::Created().json(result))
+}
+
+#[put("/profiles/{profile_id}")]
+pub async fn replace_profile(
+    state: web::Data<AppState>,
+    account: web::ReqData<account::Model>,
+    path: web::Path<i32>,
+    form: web::Json<ProfileForm>,
+) -> Result<HttpResponse, AppError> {
+    let form = form.into_inner();
+    if !auth::can_edit_class(&account, form.class_of) {
+        return Err(AppError::Forbidden);
+    }
+    form.validate().map_err(AppError::Invalid)?;
+    let existing = find_profile(&state.db, path.into_inner()).await?;
+    if !auth::can_edit_class(&account, existing.class_of) {
+        return Err(AppError::Forbidden);
+    }
+    check_university(&state.db, form.university_id).await?;
+    check_base(&state.db, form.base_id).await?;
+    let mut model: profile::ActiveModel = existing.into();
+    form.apply(&mut model);
+    let result = model.update(&state.db).await?;
+    Ok(HttpResponse::Ok().json(result))
+}
+
+#[patch("/profiles/{profile_id}")]
+pub async fn update_profile(
+    state: web::Data<AppState>,
+    account: web::ReqData<account::Model>,
+    path: web::Path<i32>,
+    body: web::Json<ProfilePatch>,
+) -> Result<HttpResponse, AppError> {
+    let changes = body.into_inner();
+    if changes
+        .class_of
+        .is_some_and(|class_of| !auth::can_edit_class(&account, class_of))
+    {
+        return Err(AppError::Forbidden);
+    }
+    changes.validate().map_err(AppError::Invalid)?;
+    let existing = find_profile(&state.db, path.into_inner()).await?;
+    if !auth::can_edit_class(&account, existing.class_of) {
+        return Err(AppError::Forbidden);
+    }
+    if let Some(university_id) = changes.university_id {
+        check_university(&state.db, university_id).await?;
+    }
+    if let Some(base_id) = changes.base_id {
+        check_base(&state.db, base_id).await?;
+    }
+    let mut model: profile::ActiveModel = existing.into();
+    changes.apply(&mut model);
+    let result = model.update(&state.db).await?;
+    Ok(HttpResponse::Ok().json(result))
+}
+
+#[delete("/profiles/{profile_id}")]
+pub async fn delete_profile(
+    state: web::Data<AppState>,
+    account: web::ReqData<account::Model>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let existing = find_profile(&state.db, path.into_inner()).await?;
+    if !auth::can_edit_class(&account, existing.class_of) {
+        return Err(AppError::Forbidden);
+    }
+    profile::Entity::delete_by_id(existing.id)
+        .exec(&state.db)
+        .await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Profiles of a university matching the filter, without the contacts the viewer may not see
+async fn visible_profiles(
+    db: &DatabaseConnection,
+    viewer: Option<&account::Model>,
+    uni_id: i32,
+    filter: &ProfileFilter,
+) -> Result<Vec<profile::Model>, DbErr> {
+    let mut query = profile::Entity::find().filter(profile::Column::UniversityId.eq(uni_id));
+    if let Some(base_id) = filter.base_id {
+        query = query.filter(bases::cohort(db, base_id).await?);
+    }
+    if let Some(class_of) = filter.class_of {
+        query = query.filter(profile::Column::ClassOf.eq(class_of));
+    }
+    let mut list = query.all(db).await?;
+    for each in &mut list {
+        auth::redact_contacts(viewer, each);
+    }
+    Ok(list)
+}
+
+/// Every university, or only those with alumni of the given class
+///
+/// Their numbers of alumni are left to `/alumni_counts`, so the shape stays the same either way.
+async fn universities_of_class(
+    db: &DatabaseConnection,
+    class_of: Option<i32>,
+) -> Result<Vec<university::Model>, DbErr> {
+    match class_of {
+        Some(class_of) => Ok(universities_with_alumni(db, Some(class_of))
+            .await?
+            .into_iter()
+            .map(|each| each.university)
+            .collect()),
+        None => university::Entity::find().all(db).await,
+    }
+}
+
+/// Universities with how many alumni went there
+///
+/// With a class given, only its alumni are counted and universities without any are left out.
+async fn universities_with_alumni(
+    db: &DatabaseConnection,
+    class_of: Option<i32>,
+) -> Result<Vec<university::WithAlumni>, DbErr> {
+    let mut counted = profile::Entity::find();
+    if let Some(class_of) = class_of {
+        counted = counted.filter(profile::Column::ClassOf.eq(class_of));
+    }
+    let counts: Vec<(i32, i64)> = counted
+        .select_only()
+        .column(profile::Column::UniversityId)
+        .column_as(profile::Column::Id.count(), "alumni")
+        .group_by(profile::Column::UniversityId)
+        .into_tuple()
+        .all(db)
+        .await?;
+    let mut query = university::Entity::find().order_by_asc(university::Column::Id);
+    if class_of.is_some() {
+        query = query.filter(university::Column::Id.is_in(counts.iter().map(|(id, _)| *id)));
+    }
+    let list = query.all(db).await?;
+    Ok(list
+        .into_iter()
+        .map(|university| {
+            let alumni = counts
+                .iter()
+                .find(|(id, _)| *id == university.id)
+                .map_or(0, |(_, alumni)| *alumni);
+            university::WithAlumni { university, alumni }
+        })
+        .collect())
+}
+
+async fn find_university(
+    db: &DatabaseConnection,
+    uni_id: i32,
+) -> Result<university::Model, AppError> {
+    university::Entity::find_by_id(uni_id)
+        .one(db)
+        .await?
+        .ok_or(AppError::NotFound("No such university"))
+}
+
+async fn find_profile(
+    db: &DatabaseConnection,
+    profile_id: i32,
+) -> Result<profile::Model, AppError> {
+    profile::Entity::find_by_id(profile_id)
+        .one(db)
+        .await?
+        .ok_or(AppError::NotFound("No such profile"))
+}
+
+async fn find_default_base(db: &DatabaseConnection) -> Result<base::Model, AppError> {
+    bases::default_base(db)
+        .await?
+        .ok_or(AppError::NotFound("No base point yet"))
+}
+
+/// Makes sure a profile refers to an existing university
+async fn check_university(db: &DatabaseConnection, uni_id: i32) -> Result<(), AppError> {
+    let found = university::Entity::find_by_id(uni_id).one(db).await?;
+    if found.is_none() {
+        return Err(AppError::Invalid(vec![FieldError::new(
+            "university_id",
+            "no such university",
+        )]));
+    }
+    Ok(())
+}
+
+/// Makes sure a profile refers to an existing base point, if any
+async fn check_base(db: &DatabaseConnection, base_id: Option<i32>) -> Result<(), AppError> {
+    let Some(base_id) = base_id else {
+        return Ok(());
+    };
+    let found = base::Entity::find_by_id(base_id).one(db).await?;
+    if found.is_none() {
+        return Err(AppError::Invalid(vec![FieldError::new(
+            "base_id",
+            "no such base point",
+        )]));
+    }
+    Ok(())
+}
@@ -0,0 +1,72 @@
+//! Uploading avatars of profiles and icons of universities
+//!
+//! Both take a multipart form with the picture in an `image` field, and answer with the updated
+//! entry.
+
+use actix_multipart::form::bytes::Bytes;
+use actix_multipart::form::MultipartForm;
+use actix_web::{post, web, HttpResponse};
+use sea_orm::ActiveModelTrait;
+use sea_orm::ActiveValue::Set;
+use std::path::PathBuf;
+
+use super::{find_profile, find_university};
+use crate::auth;
+use crate::error::AppError;
+use crate::images::{self, Kind};
+use crate::server::AppState;
+use entity::account::{self, Role};
+use entity::{profile, university};
+
+/// Body of every upload
+#[derive(MultipartForm)]
+pub struct ImageUpload {
+    image: Bytes,
+}
+
+#[post("/profiles/{profile_id}/avatar")]
+pub async fn upload_avatar(
+    state: web::Data<AppState>,
+    account: web::ReqData<account::Model>,
+    path: web::Path<i32>,
+    form: MultipartForm<ImageUpload>,
+) -> Result<HttpResponse, AppError> {
+    let existing = find_profile(&state.db, path.into_inner()).await?;
+    if !auth::can_edit_class(&account, existing.class_of) {
+        return Err(AppError::Forbidden);
+    }
+    let filename = process(&state, Kind::Avatar, form.into_inner()).await?;
+    let mut model: profile::ActiveModel = existing.into();
+    model.avatar = Set(filename);
+    let result = model.update(&state.db).await?;
+    Ok(HttpResponse::Ok().json(result))
+}
+
+#[post("/universities/{uni_id}/icon")]
+pub async fn upload_icon(
+    state: web::Data<AppState>,
+    account: web::ReqData<account::Model>,
+    path: web::Path<i32>,
+    form: MultipartForm<ImageUpload>,
+) -> Result<HttpResponse, AppError> {
+    if account.role != Role::Admin {
+        return Err(AppError::Forbidden);
+    }
+    let existing = find_university(&state.db, path.into_inner()).await?;
+    let filename = process(&state, Kind::Icon, form.into_inner()).await?;
+    let mut model: university::ActiveModel = existing.into();
+    model.icon = Set(filename);
+    let result = model.update(&state.db).await?;
+    Ok(HttpResponse::Ok().json(result))
+}
+
+/// Decodes, resizes and stores the picture off the async runtime, returning its filename
+async fn process(state: &AppState, kind: Kind, upload: ImageUpload) -> Result<String, AppError> {
+    let assets_root = PathBuf::from(&state.assets_root);
+    web::block(move || {
+        let picture = images::decode(&upload.image.data)?;
+        images::store(&assets_root, kind, &picture)
+    })
+    .await
+    .map_err(|err| AppError::Internal(format!("Cannot process picture: {err}")))?
+}
@@ -0,0 +1,65 @@
+//! Version 2 of the read-only routes
+//!
+//! Same as version 1, except that bodies are plain JSON instead of JSON encoded in a string, and
+//! `/universities/{uni_id}` gives the whole university instead of only its title. `/base` gives the
+//! default base point as it is, and `/profiles/{uni_id}` can be narrowed down to one base point and
+//! graduating class.
+
+use actix_web::{get, web, HttpResponse};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+use super::{
+    find_default_base, find_university, universities_of_class, visible_profiles, ClassQuery,
+    ProfileFilter,
+};
+use crate::error::AppError;
+use crate::server::AppState;
+use entity::{account, university};
+
+/// With `class_of`, only universities with alumni from that year
+#[get("/universities")]
+pub async fn universities(
+    state: web::Data<AppState>,
+    query: web::Query<ClassQuery>,
+) -> Result<HttpResponse, AppError> {
+    let list = universities_of_class(&state.db, query.class_of).await?;
+    Ok(HttpResponse::Ok().json(list))
+}
+
+#[get("/base")]
+pub async fn get_base(state: web::Data<AppState>) -> Result<HttpResponse, AppError> {
+    let found = find_default_base(&state.db).await?;
+    Ok(HttpResponse::Ok().json(found))
+}
+
+#[get("/universities/{uni_id}")]
+pub async fn get_university(
+    state: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let found = find_university(&state.db, path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(found))
+}
+
+#[get("/profiles/{uni_id}")]
+pub async fn profiles(
+    state: web::Data<AppState>,
+    viewer: Option<web::ReqData<account::Model>>,
+    path: web::Path<i32>,
+    filter: web::Query<ProfileFilter>,
+) -> Result<HttpResponse, AppError> {
+    let list = visible_profiles(&state.db, viewer.as_deref(), path.into_inner(), &filter).await?;
+    Ok(HttpResponse::Ok().json(list))
+}
+
+#[get("/search/universities/{search_text}")]
+pub async fn search_university(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let list = university::Entity::find()
+        .filter(university::Column::Title.contains(path.into_inner()))
+        .all(&state.db)
+        .await?;
+    Ok(HttpResponse::Ok().json(list))
+}
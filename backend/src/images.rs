@@ -0,0 +1,123 @@
+//! Turns uploaded pictures into the square thumbnails served as avatars and icons
+//!
+//! Every upload is cropped to a centred square and stored in two sizes, one for the profile card
+//! and one for popups. Files are named after the hash of their content, so uploading the same
+//! picture twice reuses the same files and browsers may cache them forever.
+
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat, ImageReader, Limits};
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+use std::io::Cursor;
+use std::path::Path;
+
+use crate::error::AppError;
+use crate::forms::FieldError;
+
+/// Largest upload accepted, in bytes
+pub const MAX_UPLOAD_SIZE: usize = 5 * 1024 * 1024;
+/// Edge of the stored picture, in pixels
+pub const LARGE_SIZE: u32 = 256;
+/// Edge of the thumbnail shown on profile cards, in pixels
+pub const SMALL_SIZE: u32 = 75;
+
+/// Formats accepted for uploads
+const ACCEPTED: [ImageFormat; 3] = [ImageFormat::Png, ImageFormat::Jpeg, ImageFormat::WebP];
+/// Largest width or height accepted before decoding, to refuse decompression bombs
+const MAX_DIMENSION: u32 = 8192;
+
+/// Which kind of picture is stored, deciding its directory under `assets_root`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Avatar,
+    Icon,
+}
+
+impl Kind {
+    #[must_use]
+    pub fn directory(self) -> &'static str {
+        match self {
+            Kind::Avatar => "avatars",
+            Kind::Icon => "icons",
+        }
+    }
+}
+
+/// Name of the smaller copy stored next to `filename`
+///
+/// For `abc.png` this is `abc-75.png`.
+#[must_use]
+pub fn small_name(filename: &str) -> String {
+    match filename.rsplit_once('.') {
+        Some((stem, ext)) => format!("{stem}-{SMALL_SIZE}.{ext}"),
+        None => format!("{filename}-{SMALL_SIZE}"),
+    }
+}
+
+/// Decodes an uploaded picture, checking its format and dimensions
+///
+/// # Errors
+/// Returns [`AppError::Invalid`] on the `image` field if the picture is too large, of an
+/// unsupported format or cannot be decoded.
+pub fn decode(bytes: &[u8]) -> Result<DynamicImage, AppError> {
+    let invalid = |message: &str| AppError::Invalid(vec![FieldError::new("image", message)]);
+    if bytes.len() > MAX_UPLOAD_SIZE {
+        return Err(invalid("must be at most 5 MiB"));
+    }
+    let mut reader = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|_| invalid("cannot be read"))?;
+    if !reader
+        .format()
+        .is_some_and(|format| ACCEPTED.contains(&format))
+    {
+        return Err(invalid("must be a PNG, JPEG or WebP picture"));
+    }
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+    reader.limits(limits);
+    reader.decode().map_err(|err| invalid(&err.to_string()))
+}
+
+/// Crops the picture to a centred square and scales it to `size`
+#[must_use]
+pub fn thumbnail(picture: &DynamicImage, size: u32) -> DynamicImage {
+    let edge = picture.width().min(picture.height());
+    let x = (picture.width() - edge) / 2;
+    let y = (picture.height() - edge) / 2;
+    picture
+        .crop_imm(x, y, edge, edge)
+        .resize_exact(size, size, FilterType::Lanczos3)
+}
+
+/// Stores both sizes of the picture as PNG, returning the filename of the larger one
+///
+/// # Errors
+/// Fails if the directory cannot be created or the files cannot be written.
+pub fn store(assets_root: &Path, kind: Kind, picture: &DynamicImage) -> Result<String, AppError> {
+    let large = encode(&thumbnail(picture, LARGE_SIZE))?;
+    let small = encode(&thumbnail(picture, SMALL_SIZE))?;
+    let hash = Sha256::digest(&large)
+        .iter()
+        .take(16)
+        .fold(String::new(), |mut hash, byte| {
+            let _ = write!(hash, "{byte:02x}");
+            hash
+        });
+    let filename = format!("{hash}.png");
+
+    let directory = assets_root.join(kind.directory());
+    std::fs::create_dir_all(&directory)?;
+    std::fs::write(directory.join(&filename), large)?;
+    std::fs::write(directory.join(small_name(&filename)), small)?;
+    Ok(filename)
+}
+
+fn encode(picture: &DynamicImage) -> Result<Vec<u8>, AppError> {
+    let mut bytes = Vec::new();
+    picture
+        .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+        .map_err(|err| AppError::Internal(format!("Cannot encode picture: {err}")))?;
+    Ok(bytes)
+}
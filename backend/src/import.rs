@@ -0,0 +1,489 @@
+//! Bulk import of universities, base points and profiles from CSV or JSON files
+//!
+//! A JSON file holds every kind at once:
+//! ```json
+//! { "universities": [ ... ], "bases": [ ... ], "profiles": [ ... ] }
+//! ```
+//! while a CSV file holds only one kind, named on the command line, with a header row naming the
+//! columns.
+//!
+//! Profiles refer to their university and base point by title, which may be one already in the
+//! database or one imported in the same run. Profiles naming no base point belong to the default
+//! one. Every row is checked before anything is written, and everything is
+//! written in a single transaction.
+
+use sea_orm::ActiveValue::Set;
+use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, TransactionTrait};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::Path;
+
+use crate::error::AppError;
+use crate::forms::{BaseForm, ProfileForm, UniversityForm};
+use entity::base;
+use entity::profile::{self, Visibility};
+use entity::university;
+
+/// A profile as written in an import file
+#[derive(Debug, Deserialize)]
+struct ProfileRecord {
+    name_primary: String,
+    #[serde(default)]
+    name_supplementary: Option<String>,
+    avatar: String,
+    class_of: i32,
+    /// Title of the university
+    university: String,
+    /// Title of the base point
+    #[serde(default)]
+    base: Option<String>,
+    #[serde(default)]
+    major: Option<String>,
+    #[serde(default)]
+    bio: Option<String>,
+    #[serde(default)]
+    email: Option<String>,
+    #[serde(default)]
+    qq: Option<String>,
+    #[serde(default)]
+    wechat: Option<String>,
+    #[serde(default)]
+    matrix: Option<String>,
+    #[serde(default)]
+    contact_visibility: Visibility,
+}
+
+impl ProfileRecord {
+    /// Splits off the university and base point titles, which are resolved to ids later
+    fn into_entry(self) -> ProfileEntry {
+        let form = ProfileForm {
+            name_primary: self.name_primary,
+            name_supplementary: self.name_supplementary,
+            avatar: self.avatar,
+            class_of: self.class_of,
+            university_id: 0,
+            base_id: None,
+            major: self.major,
+            bio: self.bio,
+            email: self.email,
+            qq: self.qq,
+            wechat: self.wechat,
+            matrix: self.matrix,
+            contact_visibility: self.contact_visibility,
+        };
+        ProfileEntry {
+            form,
+            university: self.university,
+            base: self.base.filter(|title| !title.is_empty()),
+        }
+    }
+}
+
+/// A profile whose university and base point are still referred to by title
+struct ProfileEntry {
+    form: ProfileForm,
+    university: String,
+    base: Option<String>,
+}
+
+/// Layout of a JSON import file
+///
+/// Entries are kept raw at first so we know which line each of them starts on.
+#[derive(Deserialize)]
+struct Document<'a> {
+    #[serde(borrow, default)]
+    universities: Vec<&'a RawValue>,
+    #[serde(borrow, default)]
+    bases: Vec<&'a RawValue>,
+    #[serde(borrow, default)]
+    profiles: Vec<&'a RawValue>,
+}
+
+/// Something to import, remembering where it came from
+struct Row<T> {
+    line: usize,
+    record: T,
+}
+
+/// A problem found in the import file
+#[derive(Debug)]
+pub struct Problem {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Everything read from an import file
+#[derive(Default)]
+struct Parsed {
+    universities: Vec<Row<UniversityForm>>,
+    bases: Vec<Row<BaseForm>>,
+    profiles: Vec<Row<ProfileEntry>>,
+    problems: Vec<Problem>,
+}
+
+/// An import file read and checked against the database, ready to be written
+pub struct Import {
+    parsed: Parsed,
+    /// University titles profiles may refer to, entries in the file get their id once inserted
+    titles: HashMap<String, Option<i32>>,
+    /// Same for base points
+    base_titles: HashMap<String, Option<i32>>,
+}
+
+impl Import {
+    /// Reads the content of `file`, CSV files holding the given kind, and checks every entry
+    ///
+    /// # Errors
+    /// Fails if the kind of file is unknown or the database cannot be queried. Problems with the
+    /// entries themselves are kept in [`Self::problems`] instead.
+    pub async fn prepare(
+        db: &DatabaseConnection,
+        file: &Path,
+        content: &str,
+        kind: Option<&str>,
+    ) -> Result<Self, AppError> {
+        let mut parsed = parse(file, content, kind)?;
+        let mut titles: HashMap<String, Option<i32>> = university::Entity::find()
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|each| (each.title, Some(each.id)))
+            .collect();
+        let mut base_titles: HashMap<String, Option<i32>> = base::Entity::find()
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|each| (each.title, Some(each.id)))
+            .collect();
+        check_universities(&mut parsed, &mut titles);
+        check_bases(&mut parsed, &mut base_titles);
+        check_profiles(&mut parsed, &titles, &base_titles);
+        parsed.problems.sort_by_key(|problem| problem.line);
+        Ok(Self {
+            parsed,
+            titles,
+            base_titles,
+        })
+    }
+
+    /// Everything wrong with the file, by line
+    #[must_use]
+    pub fn problems(&self) -> &[Problem] {
+        &self.parsed.problems
+    }
+
+    /// How many entries of each kind the file holds
+    #[must_use]
+    pub fn summary(&self) -> String {
+        format!(
+            "{} universities, {} base points and {} profiles",
+            self.parsed.universities.len(),
+            self.parsed.bases.len(),
+            self.parsed.profiles.len()
+        )
+    }
+
+    /// Inserts every entry in a single transaction, rolled back in the end with `dry_run`
+    ///
+    /// # Errors
+    /// Fails if the file has any problems, or if an entry cannot be inserted.
+    pub async fn write(self, db: &DatabaseConnection, dry_run: bool) -> Result<(), AppError> {
+        let Self {
+            parsed,
+            mut titles,
+            mut base_titles,
+        } = self;
+        if !parsed.problems.is_empty() {
+            return Err(AppError::BadRequest(format!(
+                "Found {} problems, nothing imported",
+                parsed.problems.len()
+            )));
+        }
+        let txn = db.begin().await?;
+        for row in parsed.universities {
+            let title = row.record.title.clone();
+            let mut model = university::ActiveModel {
+                ..Default::default()
+            };
+            row.record.apply(&mut model);
+            let inserted = model.insert(&txn).await?;
+            titles.insert(title, Some(inserted.id));
+        }
+        for row in parsed.bases {
+            let title = row.record.title.clone();
+            let mut model = base::ActiveModel {
+                ..Default::default()
+            };
+            row.record.apply(&mut model);
+            let inserted = model.insert(&txn).await?;
+            base_titles.insert(title, Some(inserted.id));
+        }
+        for row in parsed.profiles {
+            let university_id = titles.get(&row.record.university).copied().flatten();
+            let base_id = row
+                .record
+                .base
+                .as_ref()
+                .and_then(|title| base_titles.get(title).copied().flatten());
+            let mut model = profile::ActiveModel {
+                ..Default::default()
+            };
+            row.record.form.apply(&mut model);
+            model.university_id = Set(university_id.unwrap_or_default());
+            model.base_id = Set(base_id);
+            model.insert(&txn).await?;
+        }
+
+        if dry_run {
+            txn.rollback().await?;
+        } else {
+            txn.commit().await?;
+        }
+        Ok(())
+    }
+}
+
+/// Imports the file into the database, printing every problem found
+///
+/// `kind` is only needed for CSV files, and with `dry_run` everything is checked and inserted but
+/// the transaction is rolled back in the end.
+///
+/// # Errors
+/// Fails if the file cannot be read, has any problems or cannot be written to the database.
+pub async fn run(
+    db: &DatabaseConnection,
+    file: &Path,
+    kind: Option<&str>,
+    dry_run: bool,
+) -> Result<(), AppError> {
+    let content = std::fs::read_to_string(file)?;
+    let import = Import::prepare(db, file, &content, kind).await?;
+    for problem in import.problems() {
+        eprintln!("{problem}");
+    }
+    let summary = import.summary();
+    import.write(db, dry_run).await?;
+    if dry_run {
+        println!("Would import {summary}");
+    } else {
+        println!("Imported {summary}");
+    }
+    Ok(())
+}
+
+/// Reads the file, CSV files holding the given kind
+fn parse(file: &Path, content: &str, kind: Option<&str>) -> Result<Parsed, AppError> {
+    match file.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => Ok(parse_json(content)),
+        Some("csv") => match kind {
+            Some("university") => Ok(parse_csv(
+                content,
+                |line, record: UniversityForm, parsed| {
+                    parsed.universities.push(Row { line, record });
+                },
+            )),
+            Some("base") => Ok(parse_csv(content, |line, record: BaseForm, parsed| {
+                parsed.bases.push(Row { line, record });
+            })),
+            Some("profile") => Ok(parse_csv(content, |line, record: ProfileRecord, parsed| {
+                parsed.profiles.push(Row {
+                    line,
+                    record: record.into_entry(),
+                });
+            })),
+            _ => Err(AppError::BadRequest(
+                "CSV files need --kind university, --kind base or --kind profile".to_string(),
+            )),
+        },
+        _ => Err(AppError::BadRequest(
+            "Only .json and .csv files can be imported".to_string(),
+        )),
+    }
+}
+
+/// Reads a JSON document holding universities, base points and profiles
+fn parse_json(content: &str) -> Parsed {
+    let mut parsed = Parsed::default();
+    let document = match serde_json::from_str::<Document>(content) {
+        Ok(document) => document,
+        Err(err) => {
+            parsed.problems.push(Problem {
+                line: err.line(),
+                message: err.to_string(),
+            });
+            return parsed;
+        }
+    };
+    for raw in document.universities {
+        let line = line_of(content, raw);
+        match serde_json::from_str::<UniversityForm>(raw.get()) {
+            Ok(record) => parsed.universities.push(Row { line, record }),
+            Err(err) => parsed.problems.push(Problem {
+                line: line + err.line() - 1,
+                message: err.to_string(),
+            }),
+        }
+    }
+    for raw in document.bases {
+        let line = line_of(content, raw);
+        match serde_json::from_str::<BaseForm>(raw.get()) {
+            Ok(record) => parsed.bases.push(Row { line, record }),
+            Err(err) => parsed.problems.push(Problem {
+                line: line + err.line() - 1,
+                message: err.to_string(),
+            }),
+        }
+    }
+    for raw in document.profiles {
+        let line = line_of(content, raw);
+        match serde_json::from_str::<ProfileRecord>(raw.get()) {
+            Ok(record) => parsed.profiles.push(Row {
+                line,
+                record: record.into_entry(),
+            }),
+            Err(err) => parsed.problems.push(Problem {
+                line: line + err.line() - 1,
+                message: err.to_string(),
+            }),
+        }
+    }
+    parsed
+}
+
+/// Finds the line a raw JSON value starts on
+///
+/// The value borrows from `content`, so its offset is where it points into it.
+fn line_of(content: &str, raw: &RawValue) -> usize {
+    let offset = (raw.get().as_ptr() as usize).saturating_sub(content.as_ptr() as usize);
+    content[..offset.min(content.len())].matches('\n').count() + 1
+}
+
+/// Reads a CSV file of a single kind, handing each record to `push`
+fn parse_csv<T: DeserializeOwned>(content: &str, push: fn(usize, T, &mut Parsed)) -> Parsed {
+    let mut parsed = Parsed::default();
+    let mut reader = csv::Reader::from_reader(content.as_bytes());
+    let headers = match reader.headers() {
+        Ok(headers) => headers.clone(),
+        Err(err) => {
+            parsed.problems.push(Problem {
+                line: 1,
+                message: err.to_string(),
+            });
+            return parsed;
+        }
+    };
+    for result in reader.records() {
+        let record = match result {
+            Ok(record) => record,
+            Err(err) => {
+                let line = err.position().map_or(0, csv_line);
+                parsed.problems.push(Problem {
+                    line,
+                    message: err.to_string(),
+                });
+                continue;
+            }
+        };
+        let line = record.position().map_or(0, csv_line);
+        match record.deserialize::<T>(Some(&headers)) {
+            Ok(value) => push(line, value, &mut parsed),
+            Err(err) => parsed.problems.push(Problem {
+                line,
+                message: err.to_string(),
+            }),
+        }
+    }
+    parsed
+}
+
+fn csv_line(position: &csv::Position) -> usize {
+    usize::try_from(position.line()).unwrap_or(usize::MAX)
+}
+
+/// Validates universities, and makes their titles available to profiles
+fn check_universities(parsed: &mut Parsed, titles: &mut HashMap<String, Option<i32>>) {
+    for row in &parsed.universities {
+        if let Err(errors) = row.record.validate() {
+            for error in errors {
+                parsed.problems.push(Problem {
+                    line: row.line,
+                    message: format!("{}: {}", error.field, error.message),
+                });
+            }
+        }
+        if titles.contains_key(&row.record.title) {
+            parsed.problems.push(Problem {
+                line: row.line,
+                message: format!("title: \"{}\" already exists", row.record.title),
+            });
+        } else {
+            titles.insert(row.record.title.clone(), None);
+        }
+    }
+}
+
+/// Validates base points, and makes their titles available to profiles
+fn check_bases(parsed: &mut Parsed, titles: &mut HashMap<String, Option<i32>>) {
+    for row in &parsed.bases {
+        if let Err(errors) = row.record.validate() {
+            for error in errors {
+                parsed.problems.push(Problem {
+                    line: row.line,
+                    message: format!("{}: {}", error.field, error.message),
+                });
+            }
+        }
+        if titles.contains_key(&row.record.title) {
+            parsed.problems.push(Problem {
+                line: row.line,
+                message: format!("title: \"{}\" already exists", row.record.title),
+            });
+        } else {
+            titles.insert(row.record.title.clone(), None);
+        }
+    }
+}
+
+/// Validates profiles and makes sure their universities and base points exist
+fn check_profiles(
+    parsed: &mut Parsed,
+    titles: &HashMap<String, Option<i32>>,
+    base_titles: &HashMap<String, Option<i32>>,
+) {
+    for row in &parsed.profiles {
+        if let Err(errors) = row.record.form.validate() {
+            for error in errors {
+                parsed.problems.push(Problem {
+                    line: row.line,
+                    message: format!("{}: {}", error.field, error.message),
+                });
+            }
+        }
+        if !titles.contains_key(&row.record.university) {
+            parsed.problems.push(Problem {
+                line: row.line,
+                message: format!(
+                    "university: no university titled \"{}\"",
+                    row.record.university
+                ),
+            });
+        }
+        if let Some(base) = &row.record.base {
+            if !base_titles.contains_key(base) {
+                parsed.problems.push(Problem {
+                    line: row.line,
+                    message: format!("base: no base point titled \"{base}\""),
+                });
+            }
+        }
+    }
+}
@@ -1,3 +1,15 @@
+pub mod auth;
+pub mod bases;
+pub mod cascade;
+pub mod error;
+pub mod export;
+pub mod forms;
 mod handlers;
-mod routes;
+pub mod images;
+pub mod import;
+pub mod list;
+pub mod manage;
+pub mod routes;
+pub mod search;
 pub mod server;
+pub mod tiles;
@@ -0,0 +1,252 @@
+//! Listing universities, base points and profiles in the terminal
+//!
+//! Profiles are listed together with their university in a single query. A profile whose
+//! university no longer exists is still listed, with its university shown as missing.
+
+use clap::{Args, ValueEnum};
+use sea_orm::sea_query::{Expr, Order};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
+use serde::Serialize;
+use unicode_width::UnicodeWidthStr;
+
+use crate::error::AppError;
+use entity::{base, profile, university};
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Kind {
+    University,
+    Base,
+    Profile,
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum Format {
+    /// Aligned columns for reading
+    #[default]
+    Table,
+    Json,
+    Csv,
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum Sort {
+    #[default]
+    Id,
+    /// Title of universities and base points, primary name of profiles
+    Name,
+    /// Profiles only
+    ClassOf,
+    /// Profiles only, by the title of their university
+    University,
+}
+
+#[derive(Args)]
+pub struct ListArgs {
+    kind: Kind,
+    /// Only profiles of this class
+    #[arg(long)]
+    class_of: Option<i32>,
+    /// Only this university, or profiles going there, by exact title
+    #[arg(long)]
+    university: Option<String>,
+    /// Only profiles whose university no longer exists
+    #[arg(long, conflicts_with = "university")]
+    orphaned: bool,
+    #[arg(long, value_enum, default_value_t)]
+    sort: Sort,
+    #[arg(short, long, value_enum, default_value_t)]
+    format: Format,
+}
+
+/// A profile joined with its university, as printed
+#[derive(Serialize)]
+struct ProfileRow {
+    id: i32,
+    name_primary: String,
+    name_supplementary: Option<String>,
+    class_of: i32,
+    university_id: i32,
+    /// Missing if the university no longer exists
+    university: Option<String>,
+    major: Option<String>,
+}
+
+/// Lists entries of the given kind, giving back what to print
+///
+/// # Errors
+/// Fails if the filters or the sort order do not apply to the kind, or the database cannot be
+/// queried.
+pub async fn run(db: &DatabaseConnection, args: ListArgs) -> Result<String, AppError> {
+    match args.kind {
+        Kind::University => universities(db, &args).await,
+        Kind::Base => bases(db, &args).await,
+        Kind::Profile => profiles(db, &args).await,
+    }
+}
+
+async fn universities(db: &DatabaseConnection, args: &ListArgs) -> Result<String, AppError> {
+    if args.class_of.is_some() || args.orphaned {
+        return Err(AppError::BadRequest(
+            "--class-of and --orphaned only apply to profiles".to_string(),
+        ));
+    }
+    let mut query = university::Entity::find();
+    if let Some(title) = &args.university {
+        query = query.filter(university::Column::Title.eq(title));
+    }
+    query = match args.sort {
+        Sort::Id => query.order_by_asc(university::Column::Id),
+        Sort::Name => query.order_by_asc(university::Column::Title),
+        Sort::ClassOf | Sort::University => {
+            return Err(AppError::BadRequest(
+                "Universities can only be sorted by id or name".to_string(),
+            ))
+        }
+    };
+    let list = query.all(db).await?;
+    let headers = ["ID", "TITLE", "COLOUR", "LONGITUDE", "LATITUDE", "ICON"];
+    render(args.format, &list, &headers, |each| {
+        vec![
+            each.id.to_string(),
+            each.title.clone(),
+            each.colour.clone(),
+            each.longitude.to_string(),
+            each.latitude.to_string(),
+            each.icon.clone(),
+        ]
+    })
+}
+
+async fn bases(db: &DatabaseConnection, args: &ListArgs) -> Result<String, AppError> {
+    if args.class_of.is_some() || args.orphaned || args.university.is_some() {
+        return Err(AppError::BadRequest(
+            "--class-of, --university and --orphaned do not apply to base points".to_string(),
+        ));
+    }
+    let query = match args.sort {
+        Sort::Id => base::Entity::find().order_by_asc(base::Column::Id),
+        Sort::Name => base::Entity::find().order_by_asc(base::Column::Title),
+        Sort::ClassOf | Sort::University => {
+            return Err(AppError::BadRequest(
+                "Base points can only be sorted by id or name".to_string(),
+            ))
+        }
+    };
+    let list = query.all(db).await?;
+    let headers = ["ID", "TITLE", "COLOUR", "LONGITUDE", "LATITUDE"];
+    render(args.format, &list, &headers, |each| {
+        vec![
+            each.id.to_string(),
+            each.title.clone(),
+            each.colour.clone(),
+            each.longitude.to_string(),
+            each.latitude.to_string(),
+        ]
+    })
+}
+
+async fn profiles(db: &DatabaseConnection, args: &ListArgs) -> Result<String, AppError> {
+    let mut query = profile::Entity::find().find_also_related(university::Entity);
+    if let Some(class_of) = args.class_of {
+        query = query.filter(profile::Column::ClassOf.eq(class_of));
+    }
+    if let Some(title) = &args.university {
+        query = query.filter(university::Column::Title.eq(title));
+    }
+    if args.orphaned {
+        query = query.filter(university::Column::Id.is_null());
+    }
+    query = match args.sort {
+        Sort::Id => query.order_by_asc(profile::Column::Id),
+        Sort::Name => query.order_by_asc(profile::Column::NamePrimary),
+        Sort::ClassOf => query
+            .order_by_asc(profile::Column::ClassOf)
+            .order_by_asc(profile::Column::NamePrimary),
+        Sort::University => query
+            .order_by(
+                Expr::col((university::Entity, university::Column::Title)),
+                Order::Asc,
+            )
+            .order_by_asc(profile::Column::NamePrimary),
+    };
+    let list: Vec<_> = query
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|(profile, university)| ProfileRow {
+            id: profile.id,
+            name_primary: profile.name_primary,
+            name_supplementary: profile.name_supplementary,
+            class_of: profile.class_of,
+            university_id: profile.university_id,
+            university: university.map(|each| each.title),
+            major: profile.major,
+        })
+        .collect();
+    let headers = ["ID", "NAME", "", "CLASS OF", "UNIVERSITY", "MAJOR"];
+    render(args.format, &list, &headers, |each| {
+        vec![
+            each.id.to_string(),
+            each.name_primary.clone(),
+            each.name_supplementary.clone().unwrap_or_default(),
+            each.class_of.to_string(),
+            each.university
+                .clone()
+                .unwrap_or_else(|| format!("(missing #{})", each.university_id)),
+            each.major.clone().unwrap_or_default(),
+        ]
+    })
+}
+
+/// Renders the list in the given format, `headers` and `cells` giving the columns of the table
+fn render<T: Serialize>(
+    format: Format,
+    list: &[T],
+    headers: &[&str],
+    cells: impl Fn(&T) -> Vec<String>,
+) -> Result<String, AppError> {
+    let failed =
+        |err: &dyn std::error::Error| AppError::Internal(format!("Cannot render list: {err}"));
+    match format {
+        Format::Table => {
+            let header = headers.iter().map(ToString::to_string).collect();
+            let rows: Vec<_> = std::iter::once(header)
+                .chain(list.iter().map(cells))
+                .collect();
+            Ok(table(&rows))
+        }
+        Format::Json => serde_json::to_string_pretty(list)
+            .map(|rendered| rendered + "\n")
+            .map_err(|err| failed(&err)),
+        Format::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            for each in list {
+                writer.serialize(each).map_err(|err| failed(&err))?;
+            }
+            let written = writer.into_inner().map_err(|err| failed(err.error()))?;
+            String::from_utf8(written).map_err(|err| failed(&err))
+        }
+    }
+}
+
+/// Lays out rows in columns padded to the widest cell, counting wide characters twice
+fn table(rows: &[Vec<String>]) -> String {
+    let mut widths = Vec::new();
+    for row in rows {
+        widths.resize(widths.len().max(row.len()), 0);
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.width());
+        }
+    }
+    let mut output = String::new();
+    for row in rows {
+        let mut line = String::new();
+        for (cell, width) in row.iter().zip(&widths) {
+            line.push_str(cell);
+            line.push_str(&" ".repeat(width - cell.width() + 2));
+        }
+        output.push_str(line.trim_end());
+        output.push('\n');
+    }
+    output
+}
@@ -1,12 +1,20 @@
 use clap::{Parser, Subcommand};
 use sea_orm::ActiveValue::Set;
-use sea_orm::{ColumnTrait, Database, EntityTrait, QueryFilter};
+use sea_orm::{ActiveEnum, ColumnTrait, Database, EntityTrait, QueryFilter};
 use std::io::Write;
-use std::path::PathBuf;
-use std::{error::Error, io, str::FromStr};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::{io, str::FromStr};
 
-use backend::server;
-use entity::{profile, university};
+use backend::cascade::OnUniversityDelete;
+use backend::error::AppError;
+use backend::export::{Dataset, Format};
+use backend::forms::{self, BaseForm};
+use backend::{auth, bases, import, list, manage, server, tiles};
+use entity::account::{self, Role};
+use entity::map::MapConfig;
+use entity::profile::{self, Visibility};
+use entity::university;
 use migration::{Migrator, MigratorTrait};
 
 mod settings;
@@ -31,43 +39,151 @@ enum Commands {
     Server,
     /// Run database migration
     Migrate,
-    /// List data from database
-    List { kind: String },
-    /// Interactively add entries in the terminal
+    /// List universities, base points or profiles
+    List(list::ListArgs),
+    /// Interactively add entries (university, profile or account) in the terminal
     Add { kind: String },
+    /// Add, edit or delete universities
+    University {
+        /// Print the resulting entry as JSON
+        #[arg(long, global = true)]
+        json: bool,
+        #[command(subcommand)]
+        command: manage::UniversityCommand,
+    },
+    /// Add, edit or delete base points
+    Base {
+        /// Print the resulting entry as JSON
+        #[arg(long, global = true)]
+        json: bool,
+        #[command(subcommand)]
+        command: manage::BaseCommand,
+    },
+    /// Add, edit or delete profiles
+    Profile {
+        /// Print the resulting entry as JSON
+        #[arg(long, global = true)]
+        json: bool,
+        #[command(subcommand)]
+        command: manage::ProfileCommand,
+    },
+    /// Import universities, base points and profiles from a JSON or CSV file
+    Import {
+        file: PathBuf,
+        /// What a CSV file holds, either university, base or profile
+        #[arg(long)]
+        kind: Option<String>,
+        /// Check and insert everything, but roll back in the end
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Cut a large picture of the world, or an .mbtiles file, into tiles in the assets directory
+    Tiles {
+        source: PathBuf,
+        /// Deepest zoom level to cut, the last one no wider than the picture if not given
+        #[arg(long)]
+        max_zoom: Option<u32>,
+    },
+    /// Export universities, base points and profiles
+    Export {
+        #[arg(short, long, value_enum, default_value_t)]
+        format: Format,
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
 }
 
 #[actix_web::main]
-async fn main() -> Result<(), Box<dyn Error>> {
+async fn main() -> ExitCode {
+    env_logger::init_from_env(env_logger::Env::default().default_filter_or("info,sqlx=warn"));
     let args = Cli::parse();
+    match run(args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
 
-    let settings = settings::Settings::new(&args.config);
+/// Runs the chosen command
+async fn run(args: Cli) -> Result<(), AppError> {
+    let settings = settings::Settings::new(&args.config)?;
 
     let db_uri = settings.general.database_uri;
     let assets_root = settings.general.assets_root;
-    let base_point = settings.base.into();
+    let base_point = settings.base;
 
     let addr = settings.general.addr;
     let port = settings.general.port;
+    let on_university_delete = settings.general.on_university_delete;
+    let map = settings.map;
 
     match args.command {
-        Commands::Server => run_server(&db_uri, &assets_root, base_point, addr, port).await,
-        Commands::Migrate => run_migration(&db_uri).await,
-        Commands::List { kind } => list(&db_uri, &kind).await,
+        Commands::Server => {
+            run_server(
+                &db_uri,
+                &assets_root,
+                base_point,
+                on_university_delete,
+                map,
+                addr,
+                port,
+            )
+            .await
+        }
+        Commands::Migrate => run_migration(&db_uri, base_point).await,
+        Commands::List(args) => {
+            let db = Database::connect(&db_uri).await?;
+            print!("{}", list::run(&db, args).await?);
+            Ok(())
+        }
         Commands::Add { kind } => interactive_add(&db_uri, &kind).await,
-    }?;
-
-    Ok(())
+        Commands::University { json, command } => {
+            let db = Database::connect(&db_uri).await?;
+            let output = manage::university(&db, command, json, on_university_delete).await?;
+            println!("{output}");
+            Ok(())
+        }
+        Commands::Base { json, command } => {
+            let db = Database::connect(&db_uri).await?;
+            println!("{}", manage::base(&db, command, json).await?);
+            Ok(())
+        }
+        Commands::Profile { json, command } => {
+            let db = Database::connect(&db_uri).await?;
+            println!("{}", manage::profile(&db, command, json).await?);
+            Ok(())
+        }
+        Commands::Import {
+            file,
+            kind,
+            dry_run,
+        } => {
+            let db = Database::connect(&db_uri).await?;
+            import::run(&db, &file, kind.as_deref(), dry_run).await
+        }
+        Commands::Tiles { source, max_zoom } => {
+            let deepest = tiles::run(&source, Path::new(&assets_root), max_zoom).await?;
+            println!("Cut tiles up to zoom level {deepest}, set tile_zoom = {deepest} in [map]");
+            Ok(())
+        }
+        Commands::Export { format, output } => export(&db_uri, format, output.as_deref()).await,
+    }
 }
 
 /// Actually starts the server
 async fn run_server(
     uri: &str,
     assets_root: &str,
-    base_point: university::Model,
+    base_point: Option<BaseForm>,
+    on_university_delete: OnUniversityDelete,
+    map: MapConfig,
     addr: String,
     port: u16,
-) -> Result<(), Box<dyn Error>> {
+) -> Result<(), AppError> {
+    forms::validate_map(&map).map_err(AppError::Invalid)?;
     let db = Database::connect(uri).await?;
     let pending = Migrator::get_pending_migrations(&db).await?;
     if !pending.is_empty() {
@@ -75,47 +191,39 @@ async fn run_server(
         Migrator::up(&db, None).await?;
         println!("Success");
     }
+    if bases::seed(&db, base_point).await? {
+        println!("Created the base point from the config");
+    }
     println!("Running server");
-    server::run(db, assets_root, base_point, addr, port).await?;
+    server::run(db, assets_root, on_university_delete, map, addr, port).await?;
     Ok(())
 }
 
 /// Ensure the database is ready
-async fn run_migration(uri: &str) -> Result<(), Box<dyn Error>> {
+async fn run_migration(uri: &str, base_point: Option<BaseForm>) -> Result<(), AppError> {
     let db = Database::connect(uri).await?;
     println!("Running migration");
     Migrator::up(&db, None).await?;
+    if bases::seed(&db, base_point).await? {
+        println!("Created the base point from the config");
+    }
     println!("Success");
     Ok(())
 }
 
-/// See all entries
-async fn list(uri: &str, kind: &str) -> Result<(), Box<dyn Error>> {
+/// Dump everything to a file or stdout
+async fn export(uri: &str, format: Format, output: Option<&Path>) -> Result<(), AppError> {
     let db = Database::connect(uri).await?;
-    if kind.eq("university") {
-        let list = university::Entity::find().all(&db).await?;
-        for each in list {
-            println!("{}: {}", each.id, each.title);
-        }
-        Ok(())
-    } else if kind.eq("profile") {
-        let list = profile::Entity::find().all(&db).await?;
-        for each in list {
-            let university = university::Entity::find_by_id(each.university_id)
-                .one(&db)
-                .await?
-                .unwrap()
-                .title;
-            println!("{}: {}: {university}", each.id, each.name_primary);
-        }
-        Ok(())
-    } else {
-        eprintln!("Unknown kind");
-        Err(Box::new(server::AppError::RuntimeErr))
+    let rendered = Dataset::load(&db).await?.render(format)?;
+    match output {
+        Some(path) => std::fs::write(path, rendered)?,
+        None => println!("{rendered}"),
     }
+    Ok(())
 }
+
 /// Add an entry in the terminal
-async fn interactive_add(uri: &str, kind: &str) -> Result<(), Box<dyn Error>> {
+async fn interactive_add(uri: &str, kind: &str) -> Result<(), AppError> {
     let db = Database::connect(uri).await?;
     if kind.eq("university") {
         println!("Creating new University");
@@ -145,13 +253,17 @@ async fn interactive_add(uri: &str, kind: &str) -> Result<(), Box<dyn Error>> {
             .filter(university::Column::Title.contains(ask_value::<String>("University")))
             .one(&db)
             .await?
-            .expect("No such University")
+            .ok_or(AppError::NotFound("No such university"))?
             .id;
         let major = ask_value_nullable("Major");
         let bio = ask_value_nullable("Bio");
         let email = ask_value_nullable("Email");
         let qq = ask_value_nullable("QQ");
         let wechat = ask_value_nullable("Wechat");
+        let contact_visibility =
+            ask_value_nullable("Contact visibility (public/classmates/hidden)")
+                .and_then(|value| Visibility::try_from_value(&value).ok())
+                .unwrap_or_default();
         let model = profile::ActiveModel {
             name_primary: Set(name_primary),
             name_supplementary: Set(name_supplementary),
@@ -163,14 +275,37 @@ async fn interactive_add(uri: &str, kind: &str) -> Result<(), Box<dyn Error>> {
             email: Set(email),
             qq: Set(qq),
             wechat: Set(wechat),
+            contact_visibility: Set(contact_visibility),
             ..Default::default()
         };
         profile::Entity::insert(model).exec(&db).await?;
         println!("Success");
         Ok(())
+    } else if kind.eq("account") {
+        println!("Creating new Account");
+        let username = ask_value("Username");
+        let password: String = ask_value("Password");
+        let role = loop {
+            if let Ok(role) = Role::try_from_value(&ask_value("Role (admin/editor/member)")) {
+                break role;
+            }
+            eprintln!("Parse failed");
+        };
+        let class_of = ask_value_nullable("Class of").and_then(|value| value.parse().ok());
+        let password_hash = auth::hash_password(&password)
+            .map_err(|err| AppError::Internal(format!("Cannot hash password: {err}")))?;
+        let model = account::ActiveModel {
+            username: Set(username),
+            password_hash: Set(password_hash),
+            role: Set(role),
+            class_of: Set(class_of),
+            ..Default::default()
+        };
+        account::Entity::insert(model).exec(&db).await?;
+        println!("Success");
+        Ok(())
     } else {
-        eprintln!("Unknown kind");
-        Err(Box::new(server::AppError::RuntimeErr))
+        Err(AppError::BadRequest(format!("Unknown kind \"{kind}\"")))
     }
 }
 
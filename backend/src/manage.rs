@@ -0,0 +1,498 @@
+//! Scriptable maintenance of universities, base points and profiles
+//!
+//! Every change is checked like the HTTP API does. Each command gives back what to print: with
+//! `--json` the resulting entry as JSON instead of a summary line, so scripts can pick up
+//! generated ids.
+
+use clap::{ArgGroup, Args, Subcommand};
+use sea_orm::{
+    ActiveEnum, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter,
+};
+use serde::Serialize;
+
+use crate::bases;
+use crate::cascade::{self, OnUniversityDelete};
+use crate::error::AppError;
+use crate::forms::{
+    BaseForm, BasePatch, FieldError, ProfileForm, ProfilePatch, UniversityForm, UniversityPatch,
+};
+use entity::base;
+use entity::profile::{self, Visibility};
+use entity::university;
+
+#[derive(Subcommand)]
+pub enum UniversityCommand {
+    /// Create a university
+    Add(UniversityArgs),
+    /// Change the given fields of a university
+    Edit {
+        id: i32,
+        #[command(flatten)]
+        changes: UniversityChanges,
+    },
+    /// Remove a university
+    Delete {
+        id: i32,
+        /// Delete its profiles too, whatever `on_university_delete` says
+        #[arg(long)]
+        cascade: bool,
+    },
+}
+
+#[derive(Args)]
+pub struct UniversityArgs {
+    #[arg(long)]
+    title: String,
+    /// File name under assets/icons
+    #[arg(long)]
+    icon: String,
+    /// Like #RRGGBB or #RRGGBBAA
+    #[arg(long)]
+    colour: String,
+    #[arg(long, allow_hyphen_values = true)]
+    lon: f32,
+    #[arg(long, allow_hyphen_values = true)]
+    lat: f32,
+}
+
+#[derive(Args)]
+pub struct UniversityChanges {
+    #[arg(long)]
+    title: Option<String>,
+    #[arg(long)]
+    icon: Option<String>,
+    #[arg(long)]
+    colour: Option<String>,
+    #[arg(long, allow_hyphen_values = true)]
+    lon: Option<f32>,
+    #[arg(long, allow_hyphen_values = true)]
+    lat: Option<f32>,
+}
+
+#[derive(Subcommand)]
+pub enum BaseCommand {
+    /// Create a base point
+    Add(BaseArgs),
+    /// Change the given fields of a base point
+    Edit {
+        id: i32,
+        #[command(flatten)]
+        changes: BaseChanges,
+    },
+    /// Remove a base point, refused while profiles set out from it unless they are reassigned
+    Delete {
+        id: i32,
+        /// Move its profiles to this base point
+        #[arg(long)]
+        reassign_to: Option<i32>,
+    },
+}
+
+#[derive(Args)]
+pub struct BaseArgs {
+    #[arg(long)]
+    title: String,
+    /// Like #RRGGBB or #RRGGBBAA
+    #[arg(long)]
+    colour: String,
+    #[arg(long, allow_hyphen_values = true)]
+    lon: f32,
+    #[arg(long, allow_hyphen_values = true)]
+    lat: f32,
+}
+
+#[derive(Args)]
+pub struct BaseChanges {
+    #[arg(long)]
+    title: Option<String>,
+    #[arg(long)]
+    colour: Option<String>,
+    #[arg(long, allow_hyphen_values = true)]
+    lon: Option<f32>,
+    #[arg(long, allow_hyphen_values = true)]
+    lat: Option<f32>,
+}
+
+#[derive(Subcommand)]
+pub enum ProfileCommand {
+    /// Create a profile
+    Add(ProfileArgs),
+    /// Change the given fields of a profile, an empty value clears an optional field
+    Edit {
+        id: i32,
+        #[command(flatten)]
+        changes: ProfileChanges,
+    },
+    /// Remove a profile
+    Delete { id: i32 },
+}
+
+#[derive(Args)]
+#[command(group(ArgGroup::new("target").required(true).args(["university", "university_id"])))]
+pub struct ProfileArgs {
+    #[arg(long)]
+    name: String,
+    #[arg(long)]
+    name_supplementary: Option<String>,
+    /// File name under assets/avatars
+    #[arg(long)]
+    avatar: String,
+    #[arg(long)]
+    class_of: i32,
+    /// Exact title of the university
+    #[arg(long)]
+    university: Option<String>,
+    #[arg(long)]
+    university_id: Option<i32>,
+    /// Base point the alumnus set out from, the default one if missing
+    #[arg(long)]
+    base_id: Option<i32>,
+    #[arg(long)]
+    major: Option<String>,
+    #[arg(long)]
+    bio: Option<String>,
+    #[arg(long)]
+    email: Option<String>,
+    #[arg(long)]
+    qq: Option<String>,
+    #[arg(long)]
+    wechat: Option<String>,
+    #[arg(long)]
+    matrix: Option<String>,
+    /// Who may see the contacts: public, classmates or hidden
+    #[arg(long, value_parser = parse_visibility)]
+    visibility: Option<Visibility>,
+}
+
+#[derive(Args)]
+#[command(group(ArgGroup::new("target").args(["university", "university_id"])))]
+pub struct ProfileChanges {
+    #[arg(long)]
+    name: Option<String>,
+    #[arg(long)]
+    name_supplementary: Option<String>,
+    #[arg(long)]
+    avatar: Option<String>,
+    #[arg(long)]
+    class_of: Option<i32>,
+    /// Exact title of the university
+    #[arg(long)]
+    university: Option<String>,
+    #[arg(long)]
+    university_id: Option<i32>,
+    #[arg(long)]
+    base_id: Option<i32>,
+    /// Move the profile to the default base point
+    #[arg(long, conflicts_with = "base_id")]
+    default_base: bool,
+    #[arg(long)]
+    major: Option<String>,
+    #[arg(long)]
+    bio: Option<String>,
+    #[arg(long)]
+    email: Option<String>,
+    #[arg(long)]
+    qq: Option<String>,
+    #[arg(long)]
+    wechat: Option<String>,
+    #[arg(long)]
+    matrix: Option<String>,
+    /// Who may see the contacts: public, classmates or hidden
+    #[arg(long, value_parser = parse_visibility)]
+    visibility: Option<Visibility>,
+}
+
+/// Runs a `university` subcommand
+///
+/// # Errors
+/// Fails if the changes are invalid, the university does not exist or the database cannot be
+/// written.
+pub async fn university(
+    db: &DatabaseConnection,
+    command: UniversityCommand,
+    json: bool,
+    on_delete: OnUniversityDelete,
+) -> Result<String, AppError> {
+    match command {
+        UniversityCommand::Add(args) => {
+            let form = UniversityForm {
+                title: args.title,
+                icon: args.icon,
+                colour: args.colour,
+                longitude: args.lon,
+                latitude: args.lat,
+            };
+            form.validate().map_err(AppError::Invalid)?;
+            let mut model = university::ActiveModel {
+                ..Default::default()
+            };
+            form.apply(&mut model);
+            let result = model.insert(db).await?;
+            report(json, &result, || {
+                format!("Added university {}: {}", result.id, result.title)
+            })
+        }
+        UniversityCommand::Edit { id, changes } => {
+            let patch = UniversityPatch {
+                title: changes.title,
+                icon: changes.icon,
+                colour: changes.colour,
+                longitude: changes.lon,
+                latitude: changes.lat,
+            };
+            patch.validate().map_err(AppError::Invalid)?;
+            let mut model: university::ActiveModel = university::Entity::find_by_id(id)
+                .one(db)
+                .await?
+                .ok_or(AppError::NotFound("No such university"))?
+                .into();
+            patch.apply(&mut model);
+            let result = model.update(db).await?;
+            report(json, &result, || {
+                format!("Updated university {}: {}", result.id, result.title)
+            })
+        }
+        UniversityCommand::Delete {
+            id,
+            cascade: with_profiles,
+        } => {
+            let rule = if with_profiles {
+                OnUniversityDelete::Cascade
+            } else {
+                on_delete
+            };
+            let profiles = cascade::delete_university(db, id, rule).await?;
+            let deleted = Deleted {
+                deleted: id,
+                profiles: Some(profiles),
+            };
+            report(json, &deleted, || {
+                format!("Deleted university {id} and {profiles} profiles")
+            })
+        }
+    }
+}
+
+/// Runs a `base` subcommand
+///
+/// # Errors
+/// Fails if the changes are invalid, the base point does not exist or the database cannot be
+/// written.
+pub async fn base(
+    db: &DatabaseConnection,
+    command: BaseCommand,
+    json: bool,
+) -> Result<String, AppError> {
+    match command {
+        BaseCommand::Add(args) => {
+            let form = BaseForm {
+                title: args.title,
+                colour: args.colour,
+                longitude: args.lon,
+                latitude: args.lat,
+            };
+            form.validate().map_err(AppError::Invalid)?;
+            let mut model = base::ActiveModel {
+                ..Default::default()
+            };
+            form.apply(&mut model);
+            let result = model.insert(db).await?;
+            report(json, &result, || {
+                format!("Added base point {}: {}", result.id, result.title)
+            })
+        }
+        BaseCommand::Edit { id, changes } => {
+            let patch = BasePatch {
+                title: changes.title,
+                colour: changes.colour,
+                longitude: changes.lon,
+                latitude: changes.lat,
+            };
+            patch.validate().map_err(AppError::Invalid)?;
+            let mut model: base::ActiveModel = base::Entity::find_by_id(id)
+                .one(db)
+                .await?
+                .ok_or(AppError::NotFound("No such base point"))?
+                .into();
+            patch.apply(&mut model);
+            let result = model.update(db).await?;
+            report(json, &result, || {
+                format!("Updated base point {}: {}", result.id, result.title)
+            })
+        }
+        BaseCommand::Delete { id, reassign_to } => {
+            let profiles = bases::delete_base(db, id, reassign_to).await?;
+            let deleted = Deleted {
+                deleted: id,
+                profiles: Some(profiles),
+            };
+            report(json, &deleted, || {
+                format!("Deleted base point {id}, {profiles} profiles reassigned")
+            })
+        }
+    }
+}
+
+/// Runs a `profile` subcommand
+///
+/// # Errors
+/// Fails if the changes are invalid, the profile does not exist or the database cannot be
+/// written.
+pub async fn profile(
+    db: &DatabaseConnection,
+    command: ProfileCommand,
+    json: bool,
+) -> Result<String, AppError> {
+    match command {
+        ProfileCommand::Add(args) => {
+            let university_id =
+                resolve_university(db, args.university_id, args.university.as_deref()).await?;
+            check_base(db, args.base_id).await?;
+            let form = ProfileForm {
+                name_primary: args.name,
+                name_supplementary: args.name_supplementary,
+                avatar: args.avatar,
+                class_of: args.class_of,
+                university_id: university_id.unwrap_or_default(),
+                base_id: args.base_id,
+                major: args.major,
+                bio: args.bio,
+                email: args.email,
+                qq: args.qq,
+                wechat: args.wechat,
+                matrix: args.matrix,
+                contact_visibility: args.visibility.unwrap_or_default(),
+            };
+            form.validate().map_err(AppError::Invalid)?;
+            let mut model = profile::ActiveModel {
+                ..Default::default()
+            };
+            form.apply(&mut model);
+            let result = model.insert(db).await?;
+            report(json, &result, || {
+                format!("Added profile {}: {}", result.id, result.name_primary)
+            })
+        }
+        ProfileCommand::Edit { id, changes } => {
+            let university_id =
+                resolve_university(db, changes.university_id, changes.university.as_deref())
+                    .await?;
+            check_base(db, changes.base_id).await?;
+            let base_id = if changes.default_base {
+                Some(None)
+            } else {
+                changes.base_id.map(Some)
+            };
+            let patch = ProfilePatch {
+                name_primary: changes.name,
+                name_supplementary: clearable(changes.name_supplementary),
+                avatar: changes.avatar,
+                class_of: changes.class_of,
+                university_id,
+                base_id,
+                major: clearable(changes.major),
+                bio: clearable(changes.bio),
+                email: clearable(changes.email),
+                qq: clearable(changes.qq),
+                wechat: clearable(changes.wechat),
+                matrix: clearable(changes.matrix),
+                contact_visibility: changes.visibility,
+            };
+            patch.validate().map_err(AppError::Invalid)?;
+            let mut model: profile::ActiveModel = profile::Entity::find_by_id(id)
+                .one(db)
+                .await?
+                .ok_or(AppError::NotFound("No such profile"))?
+                .into();
+            patch.apply(&mut model);
+            let result = model.update(db).await?;
+            report(json, &result, || {
+                format!("Updated profile {}: {}", result.id, result.name_primary)
+            })
+        }
+        ProfileCommand::Delete { id } => {
+            let result = profile::Entity::delete_by_id(id).exec(db).await?;
+            if result.rows_affected == 0 {
+                return Err(AppError::NotFound("No such profile"));
+            }
+            let deleted = Deleted {
+                deleted: id,
+                profiles: None,
+            };
+            report(json, &deleted, || format!("Deleted profile {id}"))
+        }
+    }
+}
+
+/// Given with `--json` after a deletion
+#[derive(Serialize)]
+struct Deleted {
+    deleted: i32,
+    /// Profiles deleted along with a university, or moved away from a base point
+    #[serde(skip_serializing_if = "Option::is_none")]
+    profiles: Option<u64>,
+}
+
+/// Renders the result as JSON, or the summary otherwise
+fn report<T: Serialize>(
+    json: bool,
+    result: &T,
+    summary: impl FnOnce() -> String,
+) -> Result<String, AppError> {
+    if json {
+        serde_json::to_string_pretty(result)
+            .map_err(|err| AppError::Internal(format!("Cannot render result: {err}")))
+    } else {
+        Ok(summary())
+    }
+}
+
+/// Finds the university given either by id or by exact title, making sure it exists
+async fn resolve_university(
+    db: &DatabaseConnection,
+    id: Option<i32>,
+    title: Option<&str>,
+) -> Result<Option<i32>, AppError> {
+    let found = match (id, title) {
+        (Some(id), _) => university::Entity::find_by_id(id).one(db).await?,
+        (None, Some(title)) => {
+            university::Entity::find()
+                .filter(university::Column::Title.eq(title))
+                .one(db)
+                .await?
+        }
+        (None, None) => return Ok(None),
+    };
+    match found {
+        Some(found) => Ok(Some(found.id)),
+        None => Err(AppError::Invalid(vec![FieldError::new(
+            "university",
+            "no such university",
+        )])),
+    }
+}
+
+/// Makes sure the base point exists, if one is given
+async fn check_base(db: &DatabaseConnection, id: Option<i32>) -> Result<(), AppError> {
+    let Some(id) = id else {
+        return Ok(());
+    };
+    if base::Entity::find_by_id(id).one(db).await?.is_none() {
+        return Err(AppError::Invalid(vec![FieldError::new(
+            "base_id",
+            "no such base point",
+        )]));
+    }
+    Ok(())
+}
+
+/// An empty value on the command line clears the field
+#[allow(clippy::option_option)]
+fn clearable(value: Option<String>) -> Option<Option<String>> {
+    value.map(|value| (!value.is_empty()).then_some(value))
+}
+
+fn parse_visibility(value: &str) -> Result<Visibility, String> {
+    Visibility::try_from_value(&value.to_string())
+        .map_err(|_| "must be public, classmates or hidden".to_string())
+}
@@ -1,17 +1,92 @@
-use actix_web::web;
+use actix_multipart::form::MultipartFormConfig;
+use actix_web::{middleware, web};
 
+use crate::auth;
+use crate::error::AppError;
+use crate::forms::FieldError;
 use crate::handlers;
+use crate::images;
 
+/// Registers every route of the app
+///
+/// Version 2 of the API lives under `/api/v2`, while `/api` stays version 1 for old clients.
+/// Both versions only differ in their read-only routes, the rest is shared.
 pub fn setup(cfg: &mut web::ServiceConfig) {
     cfg.service(
+        web::scope("/api/v2")
+            .wrap(middleware::from_fn(auth::authenticate))
+            .app_data(json_config())
+            .app_data(multipart_config())
+            .configure(v2)
+            .configure(shared),
+    )
+    .service(
         web::scope("/api")
-            .service(handlers::ping)
-            .service(handlers::base)
-            .service(handlers::get_uni_name)
-            .service(handlers::universities)
-            .service(handlers::profiles)
-            .service(handlers::search_university),
+            .wrap(middleware::from_fn(auth::authenticate))
+            .app_data(json_config())
+            .app_data(multipart_config())
+            .configure(v1)
+            .configure(shared),
     )
     .service(handlers::png)
+    .service(handlers::tile)
     .service(handlers::index);
 }
+
+/// Read-only routes answering with JSON encoded in a string
+fn v1(cfg: &mut web::ServiceConfig) {
+    cfg.service(handlers::get_base)
+        .service(handlers::get_uni_name)
+        .service(handlers::universities)
+        .service(handlers::profiles)
+        .service(handlers::search_university);
+}
+
+/// Read-only routes answering with plain JSON
+fn v2(cfg: &mut web::ServiceConfig) {
+    cfg.service(handlers::v2::get_base)
+        .service(handlers::v2::get_university)
+        .service(handlers::v2::universities)
+        .service(handlers::v2::profiles)
+        .service(handlers::v2::search_university);
+}
+
+/// Routes that are the same in every version
+fn shared(cfg: &mut web::ServiceConfig) {
+    cfg.service(handlers::ping)
+        .service(handlers::login)
+        .service(handlers::logout)
+        .service(handlers::list_bases)
+        .service(handlers::years)
+        .service(handlers::map)
+        .service(handlers::alumni_counts)
+        .service(handlers::search_all)
+        .service(handlers::export)
+        .service(handlers::create_university)
+        .service(handlers::replace_university)
+        .service(handlers::update_university)
+        .service(handlers::delete_university)
+        .service(handlers::create_profile)
+        .service(handlers::replace_profile)
+        .service(handlers::update_profile)
+        .service(handlers::delete_profile)
+        .service(handlers::upload_avatar)
+        .service(handlers::upload_icon);
+}
+
+/// Answers malformed bodies like any other validation failure
+fn json_config() -> web::JsonConfig {
+    web::JsonConfig::default().error_handler(|err, _req| {
+        AppError::Invalid(vec![FieldError::new("body", err.to_string())]).into()
+    })
+}
+
+/// Refuses uploads larger than [`images::MAX_UPLOAD_SIZE`], answering like a validation failure
+fn multipart_config() -> MultipartFormConfig {
+    MultipartFormConfig::default()
+        .total_limit(images::MAX_UPLOAD_SIZE + 64 * 1024)
+        .memory_limit(images::MAX_UPLOAD_SIZE)
+        .error_handler(|err, _req| {
+            AppError::Invalid(vec![FieldError::new("image", err.to_string())]).into()
+        })
+}
@@ -0,0 +1,85 @@
+//! Searching profiles and universities at once
+//!
+//! A query matches anywhere in a field, ignoring case. Chinese names are found by their pinyin
+//! when it is kept as the supplementary name.
+
+use sea_orm::sea_query::{Condition, Expr, Func, LikeExpr, SimpleExpr};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+};
+
+use crate::auth;
+use entity::account;
+use entity::search::{Field, Hit};
+use entity::{profile, university};
+
+/// At most this many universities and this many profiles are returned
+pub const MAX_HITS: u64 = 50;
+
+/// Universities whose titles match, then profiles whose names, majors or bios do
+///
+/// Contacts the viewer may not see are left out of the profiles.
+///
+/// # Errors
+/// Returns the error if the database cannot be queried.
+pub async fn search(
+    db: &DatabaseConnection,
+    viewer: Option<&account::Model>,
+    text: &str,
+) -> Result<Vec<Hit>, DbErr> {
+    let pattern = like_pattern(text);
+    let universities = university::Entity::find()
+        .filter(matches(university::Column::Title, &pattern))
+        .order_by_asc(university::Column::Title)
+        .limit(MAX_HITS)
+        .all(db)
+        .await?;
+    let profiles = profile::Entity::find()
+        .filter(
+            Condition::any()
+                .add(matches(profile::Column::NamePrimary, &pattern))
+                .add(matches(profile::Column::NameSupplementary, &pattern))
+                .add(matches(profile::Column::Major, &pattern))
+                .add(matches(profile::Column::Bio, &pattern)),
+        )
+        .find_also_related(university::Entity)
+        .order_by_asc(profile::Column::NamePrimary)
+        .limit(MAX_HITS)
+        .all(db)
+        .await?;
+
+    let lowered = text.to_lowercase();
+    let mut hits: Vec<Hit> = universities.into_iter().map(Hit::University).collect();
+    for (mut profile, university) in profiles {
+        let matched = Field::ALL
+            .into_iter()
+            .find(|field| field.of(&profile).to_lowercase().contains(&lowered))
+            .unwrap_or(Field::NamePrimary);
+        auth::redact_contacts(viewer, &mut profile);
+        hits.push(Hit::Profile {
+            profile,
+            university: university.map(|each| each.title).unwrap_or_default(),
+            matched,
+        });
+    }
+    Ok(hits)
+}
+
+/// `LOWER(column) LIKE pattern`, the pattern being lowercased already
+fn matches(column: impl ColumnTrait, pattern: &str) -> SimpleExpr {
+    Expr::expr(Func::lower(Expr::col((column.entity_name(), column))))
+        .like(LikeExpr::new(pattern).escape('\\'))
+}
+
+/// Lowercases the text and escapes what `LIKE` treats specially, so it matches anywhere
+fn like_pattern(text: &str) -> String {
+    let mut pattern = String::from("%");
+    for each in text.to_lowercase().chars() {
+        if matches!(each, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(each);
+    }
+    pattern.push('%');
+    pattern
+}
@@ -1,50 +1,38 @@
 use actix_web::{middleware, web, App, HttpServer};
 use sea_orm::DatabaseConnection;
-use std::{error::Error, fmt::Display};
 
+use crate::cascade::OnUniversityDelete;
+use crate::error::AppError;
 use crate::routes;
-use entity::university;
+use entity::map::MapConfig;
 
 #[derive(Clone)]
 pub struct AppState {
     pub db: DatabaseConnection,
     pub assets_root: String,
-    pub base: university::Model,
-}
-
-/// Errors that may happen in the App
-#[derive(Debug)]
-pub enum AppError {
-    /// App cannot connect to the database
-    DbErr,
-    /// App cannot listen on the specified port
-    ListenErr,
-    /// App encountered an error during runtime
-    RuntimeErr,
-}
-impl Error for AppError {}
-impl Display for AppError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Error")
-    }
+    pub on_university_delete: OnUniversityDelete,
+    /// Served to the frontend as it is
+    pub map: MapConfig,
 }
 
 /// Runs the backend app
 ///
 /// # Errors
-/// If the app fails to connect to the given database or cannot bind to the specified port, it will not run and exit immediately.
-/// If the app encounters a runtime error, it will halt and return a [`AppError::RuntimeErr`].
+/// If the app cannot bind to the specified port, it will not run and return a [`AppError::Listen`].
+/// If the app encounters a runtime error, it will halt and return a [`AppError::Io`].
 pub async fn run(
     db: DatabaseConnection,
     assets_root: &str,
-    base: university::Model,
+    on_university_delete: OnUniversityDelete,
+    map: MapConfig,
     addr: String,
     port: u16,
 ) -> Result<(), AppError> {
     let state = AppState {
         db,
         assets_root: assets_root.to_string(),
-        base,
+        on_university_delete,
+        map,
     };
     let mut server = HttpServer::new(move || {
         App::new()
@@ -52,10 +40,7 @@ pub async fn run(
             .wrap(middleware::Logger::default())
             .configure(routes::setup)
     });
-    server = server.bind((addr, port)).map_err(|_| AppError::ListenErr)?;
-    server
-        .run()
-        .await
-        .map(|()| Ok(()))
-        .map_err(|_| AppError::RuntimeErr)?
+    server = server.bind((addr, port)).map_err(AppError::Listen)?;
+    server.run().await?;
+    Ok(())
 }
@@ -2,7 +2,10 @@ use config::Config;
 use serde::Deserialize;
 use std::path::Path;
 
-use entity::university;
+use backend::cascade::OnUniversityDelete;
+use backend::error::AppError;
+use backend::forms::BaseForm;
+use entity::map::MapConfig;
 
 #[derive(Debug, Deserialize)]
 pub struct General {
@@ -10,41 +13,30 @@ pub struct General {
     pub assets_root: String,
     pub addr: String,
     pub port: u16,
-}
-
-#[derive(Debug, Deserialize)]
-pub struct Base {
-    title: String,
-    colour: String,
-    longitude: f32,
-    latitude: f32,
-}
-impl From<Base> for university::Model {
-    fn from(val: Base) -> Self {
-        university::Model {
-            id: -1,
-            title: val.title,
-            icon: String::new(),
-            colour: val.colour,
-            longitude: val.longitude,
-            latitude: val.latitude,
-        }
-    }
+    /// Whether deleting a university with profiles fails or deletes them too
+    #[serde(default)]
+    pub on_university_delete: OnUniversityDelete,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Settings {
     pub general: General,
-    pub base: Base,
+    /// Creates the first base point, later ones live in the database only
+    pub base: Option<BaseForm>,
+    /// The picture under the map, the built-in one if missing
+    #[serde(default)]
+    pub map: MapConfig,
 }
 
 impl Settings {
-    #[must_use]
-    pub fn new(location: &Path) -> Self {
+    /// Reads the config file
+    ///
+    /// # Errors
+    /// Fails if the file cannot be read or misses some settings.
+    pub fn new(location: &Path) -> Result<Self, AppError> {
         let config = Config::builder()
             .add_source(config::File::with_name(&location.to_string_lossy()))
-            .build()
-            .expect("Failed to read config");
-        config.try_deserialize().expect("Failed to parse config")
+            .build()?;
+        Ok(config.try_deserialize()?)
     }
 }
@@ -0,0 +1,200 @@
+//! Cuts a large picture of the world into tiles, so the map stays sharp when zoomed in
+//!
+//! Zoom level `z` scales the picture to `256 * 2^z` pixels wide and cuts it into squares stored
+//! as `tiles/{z}/{x}/{y}.png` under `assets_root`, padding the last row with transparency. The
+//! picture has to be the one under the map, or at least be drawn with the same projection and
+//! calibration. Tiles of `MBTiles` files are copied over as they are, which needs the `sqlite`
+//! feature.
+
+use image::imageops::{self, FilterType};
+use image::{ImageReader, RgbaImage};
+use std::path::{Path, PathBuf};
+
+use crate::error::AppError;
+use entity::map::TILE_SIZE;
+
+/// Deepest zoom level that can be cut, 65536 pixels wide
+pub const MAX_ZOOM: u32 = 8;
+
+/// Where a tile is stored
+#[must_use]
+pub fn tile_path(assets_root: &Path, zoom: u32, x: u32, y: u32) -> PathBuf {
+    assets_root
+        .join("tiles")
+        .join(zoom.to_string())
+        .join(x.to_string())
+        .join(format!("{y}.png"))
+}
+
+/// Cuts a picture or copies an `MBTiles` file, returning the deepest zoom level written
+///
+/// Without `max_zoom`, pictures are cut down to the last level that is no wider than they are.
+///
+/// # Errors
+/// Fails if the source cannot be read or a tile cannot be written.
+pub async fn run(
+    source: &Path,
+    assets_root: &Path,
+    max_zoom: Option<u32>,
+) -> Result<u32, AppError> {
+    if source.extension().is_some_and(|ext| ext == "mbtiles") {
+        return copy_mbtiles(source, assets_root, max_zoom).await;
+    }
+    cut(source, assets_root, max_zoom)
+}
+
+/// Cuts a picture into tiles of every zoom level up to the given one
+///
+/// Each tile is resized from its own part of the picture, so no level is ever held in memory as
+/// a whole, only the picture itself.
+///
+/// # Errors
+/// Fails if the picture cannot be read or a tile cannot be written.
+pub fn cut(source: &Path, assets_root: &Path, max_zoom: Option<u32>) -> Result<u32, AppError> {
+    let unreadable = |err: &dyn std::fmt::Display| {
+        AppError::BadRequest(format!("Cannot read {}: {err}", source.display()))
+    };
+    let mut reader = ImageReader::open(source)?
+        .with_guessed_format()
+        .map_err(|err| unreadable(&err))?;
+    // Pictures worth cutting are larger than the default limits allow
+    reader.no_limits();
+    let picture = reader
+        .decode()
+        .map_err(|err| unreadable(&err))?
+        .into_rgba8();
+    let max_zoom = max_zoom
+        .unwrap_or_else(|| sharpest_zoom(picture.width()))
+        .min(MAX_ZOOM);
+
+    for zoom in 0..=max_zoom {
+        let (columns, rows) = tile_count(picture.width(), picture.height(), zoom);
+        for x in 0..columns {
+            for y in 0..rows {
+                let tile = cut_tile(&picture, zoom, x, y);
+                save(&tile, &tile_path(assets_root, zoom, x, y))?;
+            }
+        }
+    }
+    Ok(max_zoom)
+}
+
+/// Numbers of columns and rows of tiles at a zoom level, for a picture of the given size
+#[must_use]
+pub fn tile_count(width: u32, height: u32, zoom: u32) -> (u32, u32) {
+    let rows = level_height(width, height, zoom).div_ceil(u64::from(TILE_SIZE));
+    (1 << zoom, u32::try_from(rows).unwrap_or(u32::MAX))
+}
+
+/// Height of a zoom level in pixels, rounded up so the last row is never empty
+fn level_height(width: u32, height: u32, zoom: u32) -> u64 {
+    (u64::from(height) * (u64::from(TILE_SIZE) << zoom))
+        .div_ceil(u64::from(width).max(1))
+        .max(1)
+}
+
+/// Deepest zoom level whose tiles are no sharper than the picture
+#[must_use]
+pub fn sharpest_zoom(width: u32) -> u32 {
+    (0..=MAX_ZOOM)
+        .rev()
+        .find(|zoom| TILE_SIZE << zoom <= width)
+        .unwrap_or(0)
+}
+
+/// Resizes the part of the picture under a tile, padding the bottom with transparency
+fn cut_tile(picture: &RgbaImage, zoom: u32, x: u32, y: u32) -> RgbaImage {
+    let (width, height) = (u64::from(picture.width()), u64::from(picture.height()));
+    let level_width = u64::from(TILE_SIZE) << zoom;
+    let level_height = level_height(picture.width(), picture.height(), zoom);
+    // Pixels of the level covered by the tile, and of the picture under them
+    let top = u64::from(y * TILE_SIZE);
+    let bottom = (top + u64::from(TILE_SIZE)).min(level_height);
+    let to_source = |pixel: u64, limit: u64| (pixel * width / level_width).min(limit);
+    let (left, right) = (
+        to_source(u64::from(x * TILE_SIZE), width - 1),
+        to_source(u64::from((x + 1) * TILE_SIZE), width),
+    );
+    let (source_top, source_bottom) = (to_source(top, height - 1), to_source(bottom, height));
+    let narrow = |value: u64| u32::try_from(value).unwrap_or(u32::MAX);
+    let piece = imageops::crop_imm(
+        picture,
+        narrow(left),
+        narrow(source_top),
+        narrow((right - left).max(1)),
+        narrow((source_bottom - source_top).max(1)),
+    );
+    let piece = imageops::resize(
+        &*piece,
+        TILE_SIZE,
+        narrow(bottom - top),
+        FilterType::Triangle,
+    );
+    let mut tile = RgbaImage::new(TILE_SIZE, TILE_SIZE);
+    imageops::overlay(&mut tile, &piece, 0, 0);
+    tile
+}
+
+fn save(tile: &RgbaImage, path: &Path) -> Result<(), AppError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    tile.save(path)
+        .map_err(|err| AppError::Internal(format!("Cannot write {}: {err}", path.display())))
+}
+
+/// Copies the tiles of an `MBTiles` file up to the given zoom level, as PNG
+#[cfg(feature = "sqlite")]
+async fn copy_mbtiles(
+    source: &Path,
+    assets_root: &Path,
+    max_zoom: Option<u32>,
+) -> Result<u32, AppError> {
+    use sea_orm::{ConnectionTrait, Database, DbBackend, Statement};
+
+    let db = Database::connect(format!("sqlite://{}?mode=ro", source.display())).await?;
+    let rows = db
+        .query_all(Statement::from_string(
+            DbBackend::Sqlite,
+            "SELECT zoom_level, tile_column, tile_row, tile_data FROM tiles",
+        ))
+        .await?;
+    let max_zoom = max_zoom.unwrap_or(MAX_ZOOM).min(MAX_ZOOM);
+    let mut deepest = 0;
+    for row in rows {
+        let zoom: u32 = row.try_get("", "zoom_level")?;
+        if zoom > max_zoom {
+            continue;
+        }
+        let x: u32 = row.try_get("", "tile_column")?;
+        // MBTiles count rows from the south
+        let row_from_south: u32 = row.try_get("", "tile_row")?;
+        let unreadable = |reason: String| {
+            AppError::BadRequest(format!(
+                "Cannot read tile {zoom}/{x}/{row_from_south}: {reason}"
+            ))
+        };
+        let y = ((1 << zoom) - 1_u32)
+            .checked_sub(row_from_south)
+            .ok_or_else(|| unreadable("no such row".to_string()))?;
+        let data: Vec<u8> = row.try_get("", "tile_data")?;
+        let tile = image::load_from_memory(&data)
+            .map_err(|err| unreadable(err.to_string()))?
+            .to_rgba8();
+        save(&tile, &tile_path(assets_root, zoom, x, y))?;
+        deepest = deepest.max(zoom);
+    }
+    Ok(deepest)
+}
+
+#[cfg(not(feature = "sqlite"))]
+#[allow(clippy::unused_async)]
+async fn copy_mbtiles(
+    _source: &Path,
+    _assets_root: &Path,
+    _max_zoom: Option<u32>,
+) -> Result<u32, AppError> {
+    Err(AppError::BadRequest(
+        "MBTiles files can only be read with the sqlite feature".to_string(),
+    ))
+}
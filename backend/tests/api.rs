@@ -0,0 +1,1262 @@
+//! Exercises every route against a fresh in-memory `SQLite` database
+
+use actix_http::Request;
+use actix_web::dev::{Service, ServiceResponse};
+use actix_web::http::{header, StatusCode};
+use actix_web::{test, web, App};
+use sea_orm::ActiveValue::Set;
+use sea_orm::{ActiveModelTrait, Database, EntityTrait};
+use serde_json::{json, Value};
+
+use backend::auth;
+use backend::bases;
+use backend::cascade::OnUniversityDelete;
+use backend::error::AppError;
+use backend::routes;
+use backend::server::AppState;
+use entity::account::{self, Role};
+use entity::map::{MapConfig, Projection};
+use entity::profile::{self, Visibility};
+use entity::search::{Field, Hit};
+use entity::{base, session, university};
+use migration::{Migrator, MigratorTrait};
+
+const PASSWORD: &str = "correct horse battery staple";
+
+/// Seeded database and a session token for each kind of account
+struct Fixture {
+    state: AppState,
+    admin: String,
+    editor: String,
+    member: String,
+}
+
+/// Two universities, two base points, a public, a classmates-only and a hidden profile, and one
+/// account per role
+///
+/// The editor maintains the class of 2025, which holds the first two profiles. Those belong to the
+/// default base point, while the third one set out from the second.
+async fn fixture() -> Fixture {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    Migrator::up(&db, None).await.unwrap();
+
+    for (title, longitude, latitude) in [("MIT", -71.09, 42.36), ("Tsinghua", 116.33, 40.0)] {
+        university::ActiveModel {
+            title: Set(title.to_string()),
+            icon: Set(format!("{title}.png")),
+            colour: Set("#A31F34".to_string()),
+            longitude: Set(longitude),
+            latitude: Set(latitude),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+    }
+    for (title, longitude) in [("Base Point", 0.0), ("East Campus", 10.0)] {
+        base::ActiveModel {
+            title: Set(title.to_string()),
+            colour: Set("#000000".to_string()),
+            longitude: Set(longitude),
+            latitude: Set(0.0),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+    }
+    for (name, class_of, university_id, base_id, visibility) in [
+        ("Alice", 2025, 1, None, Visibility::Public),
+        ("Bob", 2025, 1, None, Visibility::Classmates),
+        ("Carol", 2024, 2, Some(2), Visibility::Hidden),
+    ] {
+        profile::ActiveModel {
+            name_primary: Set(name.to_string()),
+            avatar: Set(format!("{name}.png")),
+            class_of: Set(class_of),
+            university_id: Set(university_id),
+            base_id: Set(base_id),
+            email: Set(Some(format!("{name}@example.com"))),
+            contact_visibility: Set(visibility),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+    }
+
+    let password_hash = auth::hash_password(PASSWORD).unwrap();
+    let mut tokens = Vec::new();
+    for (username, role, class_of) in [
+        ("admin", Role::Admin, None),
+        ("editor", Role::Editor, Some(2025)),
+        ("member", Role::Member, Some(2025)),
+    ] {
+        let account = account::ActiveModel {
+            username: Set(username.to_string()),
+            password_hash: Set(password_hash.clone()),
+            role: Set(role),
+            class_of: Set(class_of),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        tokens.push(auth::start_session(&db, account.id).await.unwrap().token);
+    }
+    let [admin, editor, member] = tokens.try_into().unwrap();
+
+    let assets_root = std::env::temp_dir().join(format!("alumnimap-test-{}", std::process::id()));
+    std::fs::create_dir_all(assets_root.join("icons")).unwrap();
+    std::fs::write(assets_root.join("icons/MIT.png"), b"not really a png").unwrap();
+
+    Fixture {
+        state: AppState {
+            db,
+            assets_root: assets_root.to_string_lossy().to_string(),
+            on_university_delete: OnUniversityDelete::Restrict,
+            map: MapConfig {
+                projection: Projection::Robinson,
+                image: Some("robinson.png".to_string()),
+                line_curvature: 0.25,
+                ..MapConfig::default()
+            },
+        },
+        admin,
+        editor,
+        member,
+    }
+}
+
+async fn app(
+    state: &AppState,
+) -> impl Service<Request, Response = ServiceResponse, Error = actix_web::Error> {
+    test::init_service(
+        App::new()
+            .app_data(web::Data::new(state.clone()))
+            .configure(routes::setup),
+    )
+    .await
+}
+
+fn bearer(token: &str) -> (header::HeaderName, String) {
+    (header::AUTHORIZATION, format!("Bearer {token}"))
+}
+
+/// Wraps the file in a multipart form under the `image` field
+fn multipart(file: &[u8]) -> (header::HeaderValue, Vec<u8>) {
+    let boundary = "alumnimap-boundary";
+    let mut body = format!(
+        "--{boundary}\r\nContent-Disposition: form-data; name=\"image\"; filename=\"upload\"\r\n\
+         Content-Type: application/octet-stream\r\n\r\n"
+    )
+    .into_bytes();
+    body.extend_from_slice(file);
+    body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+    let content_type =
+        header::HeaderValue::from_str(&format!("multipart/form-data; boundary={boundary}"))
+            .unwrap();
+    (content_type, body)
+}
+
+/// Reads a body the v1 routes encode as a JSON string holding JSON
+async fn read_encoded(response: ServiceResponse) -> Value {
+    let encoded: String = test::read_body_json(response).await;
+    serde_json::from_str(&encoded).unwrap()
+}
+
+#[actix_web::test]
+async fn ping() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+    let response =
+        test::call_service(&app, test::TestRequest::get().uri("/api/ping").to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(test::read_body(response).await, "Up and running");
+}
+
+#[actix_web::test]
+async fn base() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+    let response =
+        test::call_service(&app, test::TestRequest::get().uri("/api/base").to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let base = read_encoded(response).await;
+    assert_eq!(base["title"], "Base Point");
+    assert_eq!(base["id"], -1);
+}
+
+#[actix_web::test]
+async fn bases() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+    for uri in ["/api/bases", "/api/v2/bases"] {
+        let request = test::TestRequest::get().uri(uri).to_request();
+        let list: Value = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(list[0]["title"], "Base Point");
+        assert_eq!(list[0]["universities"], json!([1]));
+        assert_eq!(list[1]["title"], "East Campus");
+        assert_eq!(list[1]["universities"], json!([2]));
+    }
+
+    let request = test::TestRequest::get()
+        .uri("/api/v2/bases?class_of=2025")
+        .to_request();
+    let list: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(list[0]["universities"], json!([1]));
+    assert_eq!(list[1]["universities"], json!([]));
+}
+
+#[actix_web::test]
+async fn profiles_of_cohort() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+    for (uri, count) in [
+        ("/api/v2/profiles/1?base_id=1", 2),
+        ("/api/v2/profiles/1?base_id=2", 0),
+        ("/api/v2/profiles/2?base_id=2", 1),
+        ("/api/v2/profiles/2?base_id=1", 0),
+        ("/api/v2/profiles/1?class_of=2025", 2),
+        ("/api/v2/profiles/1?base_id=1&class_of=2024", 0),
+    ] {
+        let request = test::TestRequest::get().uri(uri).to_request();
+        let list: Value = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(list.as_array().unwrap().len(), count, "{uri}");
+    }
+}
+
+#[actix_web::test]
+async fn delete_base_in_use() {
+    let fixture = fixture().await;
+    let db = &fixture.state.db;
+    // Carol sets out from East Campus, Alice and Bob from the default base point by naming none
+    for (base_id, reassign_to) in [(2, None), (1, None)] {
+        let refused = bases::delete_base(db, base_id, reassign_to).await;
+        assert!(matches!(refused, Err(AppError::Conflict(_))), "{base_id}");
+    }
+    for reassign_to in [2, 9] {
+        let refused = bases::delete_base(db, 2, Some(reassign_to)).await;
+        assert!(
+            matches!(refused, Err(AppError::Invalid(_))),
+            "{reassign_to}"
+        );
+    }
+    let missing = bases::delete_base(db, 9, None).await;
+    assert!(matches!(missing, Err(AppError::NotFound(_))));
+    assert_eq!(base::Entity::find().all(db).await.unwrap().len(), 2);
+
+    assert_eq!(bases::delete_base(db, 2, Some(1)).await.unwrap(), 1);
+    let carol = profile::Entity::find_by_id(3)
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(carol.base_id, Some(1));
+
+    let app = app(&fixture.state).await;
+    let request = test::TestRequest::get().uri("/api/v2/bases").to_request();
+    let list: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(list.as_array().unwrap().len(), 1);
+    assert_eq!(list[0]["universities"], json!([1, 2]));
+}
+
+#[actix_web::test]
+async fn universities() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+    let request = test::TestRequest::get()
+        .uri("/api/universities")
+        .to_request();
+    let list = read_encoded(test::call_service(&app, request).await).await;
+    assert_eq!(list.as_array().unwrap().len(), 2);
+    assert_eq!(list[0]["title"], "MIT");
+}
+
+#[actix_web::test]
+async fn universities_of_class() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+    let request = test::TestRequest::get()
+        .uri("/api/universities?class_of=2025")
+        .to_request();
+    let list = read_encoded(test::call_service(&app, request).await).await;
+    assert_eq!(
+        list,
+        json!([{
+            "id": 1,
+            "title": "MIT",
+            "icon": "MIT.png",
+            "colour": "#A31F34",
+            "longitude": -71.09,
+            "latitude": 42.36,
+        }])
+    );
+
+    let request = test::TestRequest::get()
+        .uri("/api/v2/universities?class_of=2024")
+        .to_request();
+    let list: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(list.as_array().unwrap().len(), 1);
+    assert_eq!(list[0]["title"], "Tsinghua");
+    // Numbers of alumni come from /alumni_counts, so old clients keep their shape
+    assert!(list[0].get("alumni").is_none());
+
+    let request = test::TestRequest::get()
+        .uri("/api/v2/universities?class_of=1999")
+        .to_request();
+    let list: Value = test::call_and_read_body_json(&app, request).await;
+    assert!(list.as_array().unwrap().is_empty());
+}
+
+#[actix_web::test]
+async fn alumni_counts() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+    let request = test::TestRequest::post()
+        .uri("/api/universities")
+        .insert_header(bearer(&fixture.admin))
+        .set_json(json!({
+            "title": "ETH",
+            "icon": "ETH.png",
+            "colour": "#1F407A",
+            "longitude": 8.55,
+            "latitude": 47.38,
+        }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let request = test::TestRequest::get()
+        .uri("/api/v2/alumni_counts")
+        .to_request();
+    let list: Value = test::call_and_read_body_json(&app, request).await;
+    let counts: Vec<_> = list
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|each| (each["title"].clone(), each["alumni"].clone()))
+        .collect();
+    assert_eq!(
+        counts,
+        [
+            (json!("MIT"), json!(2)),
+            (json!("Tsinghua"), json!(1)),
+            (json!("ETH"), json!(0)),
+        ]
+    );
+
+    let request = test::TestRequest::get()
+        .uri("/api/alumni_counts?class_of=2024")
+        .to_request();
+    let list: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(list.as_array().unwrap().len(), 1);
+    assert_eq!(list[0]["alumni"], 1);
+}
+
+#[actix_web::test]
+async fn years() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+    let request = test::TestRequest::get().uri("/api/v2/years").to_request();
+    let list: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(
+        list,
+        json!([{"class_of": 2024, "alumni": 1}, {"class_of": 2025, "alumni": 2}])
+    );
+}
+
+#[actix_web::test]
+async fn map() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+    for uri in ["/api/map", "/api/v2/map"] {
+        let request = test::TestRequest::get().uri(uri).to_request();
+        let map: Value = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(map["projection"], "robinson");
+        assert_eq!(map["image"], "robinson.png");
+        assert_eq!(map["longitude_offset"], -150.0);
+        assert_eq!(map["line_curvature"], 0.25);
+        assert_eq!(map["line_dashes"], false);
+    }
+}
+
+#[actix_web::test]
+async fn university_name() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+    let request = test::TestRequest::get()
+        .uri("/api/universities/2")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(test::read_body(response).await, "Tsinghua");
+
+    let request = test::TestRequest::get()
+        .uri("/api/universities/9")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn search_university() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+    let request = test::TestRequest::get()
+        .uri("/api/search/universities/Tsing")
+        .to_request();
+    let list = read_encoded(test::call_service(&app, request).await).await;
+    assert_eq!(list.as_array().unwrap().len(), 1);
+    assert_eq!(list[0]["id"], 2);
+
+    let request = test::TestRequest::get()
+        .uri("/api/search/universities/Nowhere")
+        .to_request();
+    let list = read_encoded(test::call_service(&app, request).await).await;
+    assert!(list.as_array().unwrap().is_empty());
+}
+
+#[actix_web::test]
+async fn search() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+    let request = test::TestRequest::get()
+        .uri("/api/v2/search?q=mIt")
+        .to_request();
+    let hits: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(hits.as_array().unwrap().len(), 1);
+    assert_eq!(hits[0]["kind"], "university");
+    assert_eq!(hits[0]["title"], "MIT");
+
+    let request = test::TestRequest::get()
+        .uri("/api/search?q=BOB")
+        .to_request();
+    let hits: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(hits.as_array().unwrap().len(), 1);
+    assert_eq!(hits[0]["kind"], "profile");
+    assert_eq!(hits[0]["matched"], "name_primary");
+    assert_eq!(hits[0]["university_id"], 1);
+    assert_eq!(hits[0]["university"], "MIT");
+    assert_eq!(hits[0]["contacts_hidden"], true);
+
+    let request = test::TestRequest::get()
+        .uri("/api/v2/search?q=bob")
+        .insert_header(bearer(&fixture.member))
+        .to_request();
+    let hits: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(hits[0]["email"], "Bob@example.com");
+
+    // The frontend reads the same JSON back into typed hits
+    let request = test::TestRequest::get()
+        .uri("/api/v2/search?q=a")
+        .to_request();
+    let hits: Vec<Hit> = test::call_and_read_body_json(&app, request).await;
+    assert!(matches!(&hits[0], Hit::University(each) if each.title == "Tsinghua"));
+    assert!(matches!(
+        &hits[1],
+        Hit::Profile { profile, matched: Field::NamePrimary, .. } if profile.name_primary == "Alice"
+    ));
+
+    let request = test::TestRequest::get()
+        .uri("/api/v2/search?q=%25")
+        .to_request();
+    let hits: Value = test::call_and_read_body_json(&app, request).await;
+    assert!(hits.as_array().unwrap().is_empty());
+
+    let request = test::TestRequest::get()
+        .uri("/api/v2/search?q=%20")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[actix_web::test]
+async fn v2_reads_plain_json() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+
+    let request = test::TestRequest::get().uri("/api/v2/base").to_request();
+    let base: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(base["title"], "Base Point");
+
+    let request = test::TestRequest::get()
+        .uri("/api/v2/universities")
+        .to_request();
+    let list: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(list.as_array().unwrap().len(), 2);
+
+    let request = test::TestRequest::get()
+        .uri("/api/v2/universities/2")
+        .to_request();
+    let university: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(university["title"], "Tsinghua");
+    assert_eq!(university["id"], 2);
+
+    let request = test::TestRequest::get()
+        .uri("/api/v2/universities/9")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let request = test::TestRequest::get()
+        .uri("/api/v2/search/universities/Tsing")
+        .to_request();
+    let list: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(list[0]["id"], 2);
+
+    let request = test::TestRequest::get()
+        .uri("/api/v2/profiles/1")
+        .to_request();
+    let list: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(list[0]["email"], "Alice@example.com");
+    assert_eq!(list[1]["contacts_hidden"], true);
+}
+
+#[actix_web::test]
+async fn v2_shares_writes() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+
+    let request = test::TestRequest::post()
+        .uri("/api/v2/login")
+        .set_json(json!({"username": "admin", "password": PASSWORD}))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = test::TestRequest::delete()
+        .uri("/api/v2/profiles/1")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let request = test::TestRequest::delete()
+        .uri("/api/v2/profiles/1")
+        .insert_header(bearer(&fixture.admin))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+}
+
+#[actix_web::test]
+async fn profiles_hide_contacts() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+
+    let request = test::TestRequest::get().uri("/api/profiles/1").to_request();
+    let list = read_encoded(test::call_service(&app, request).await).await;
+    assert_eq!(list[0]["email"], "Alice@example.com");
+    assert!(list[1].get("email").is_none());
+    assert_eq!(list[1]["contacts_hidden"], true);
+
+    let request = test::TestRequest::get()
+        .uri("/api/profiles/1")
+        .insert_header(bearer(&fixture.member))
+        .to_request();
+    let list = read_encoded(test::call_service(&app, request).await).await;
+    assert_eq!(list[1]["email"], "Bob@example.com");
+
+    let request = test::TestRequest::get()
+        .uri("/api/profiles/2")
+        .insert_header(bearer(&fixture.editor))
+        .to_request();
+    let list = read_encoded(test::call_service(&app, request).await).await;
+    assert!(list[0].get("email").is_none());
+
+    let request = test::TestRequest::get()
+        .uri("/api/profiles/2")
+        .insert_header(bearer(&fixture.admin))
+        .to_request();
+    let list = read_encoded(test::call_service(&app, request).await).await;
+    assert_eq!(list[0]["email"], "Carol@example.com");
+
+    let request = test::TestRequest::get().uri("/api/profiles/9").to_request();
+    let list = read_encoded(test::call_service(&app, request).await).await;
+    assert!(list.as_array().unwrap().is_empty());
+}
+
+#[actix_web::test]
+async fn classmates_contacts_stay_in_class() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+    let account = account::ActiveModel {
+        username: Set("outsider".to_string()),
+        password_hash: Set(auth::hash_password(PASSWORD).unwrap()),
+        role: Set(Role::Member),
+        class_of: Set(Some(2024)),
+        ..Default::default()
+    }
+    .insert(&fixture.state.db)
+    .await
+    .unwrap();
+    let outsider = auth::start_session(&fixture.state.db, account.id)
+        .await
+        .unwrap()
+        .token;
+
+    let request = test::TestRequest::get()
+        .uri("/api/profiles/1")
+        .insert_header(bearer(&outsider))
+        .to_request();
+    let list = read_encoded(test::call_service(&app, request).await).await;
+    assert_eq!(list[0]["email"], "Alice@example.com");
+    assert!(list[1].get("email").is_none());
+    assert_eq!(list[1]["contacts_hidden"], true);
+
+    let request = test::TestRequest::get()
+        .uri("/api/profiles/1")
+        .insert_header(bearer(&fixture.admin))
+        .to_request();
+    let list = read_encoded(test::call_service(&app, request).await).await;
+    assert_eq!(list[1]["email"], "Bob@example.com");
+}
+
+#[actix_web::test]
+async fn expired_sessions_are_deleted() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+    let expire = |token: &str| session::ActiveModel {
+        token: Set(token.to_string()),
+        expires_at: Set(auth::now() - 1),
+        ..Default::default()
+    };
+    expire(&fixture.member)
+        .update(&fixture.state.db)
+        .await
+        .unwrap();
+    expire(&fixture.editor)
+        .update(&fixture.state.db)
+        .await
+        .unwrap();
+
+    let request = test::TestRequest::post()
+        .uri("/api/logout")
+        .insert_header(bearer(&fixture.member))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let found = session::Entity::find_by_id(fixture.member.clone())
+        .one(&fixture.state.db)
+        .await
+        .unwrap();
+    assert!(found.is_none());
+
+    // Logging in sweeps away the rest
+    let request = test::TestRequest::post()
+        .uri("/api/login")
+        .set_json(json!({"username": "admin", "password": PASSWORD}))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let found = session::Entity::find_by_id(fixture.editor.clone())
+        .one(&fixture.state.db)
+        .await
+        .unwrap();
+    assert!(found.is_none());
+}
+
+#[actix_web::test]
+async fn login_and_logout() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+
+    let request = test::TestRequest::post()
+        .uri("/api/login")
+        .set_json(json!({"username": "editor", "password": "wrong"}))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let request = test::TestRequest::post()
+        .uri("/api/login")
+        .set_json(json!({"username": "nobody", "password": PASSWORD}))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let request = test::TestRequest::post()
+        .uri("/api/login")
+        .set_json(json!({"username": "editor", "password": PASSWORD}))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let cookie = response
+        .response()
+        .cookies()
+        .find(|cookie| cookie.name() == auth::SESSION_COOKIE)
+        .unwrap()
+        .into_owned();
+    let body: Value = test::read_body_json(response).await;
+    assert_eq!(body["role"], "editor");
+    assert_eq!(body["token"], cookie.value());
+
+    let request = test::TestRequest::post()
+        .uri("/api/logout")
+        .cookie(cookie.clone())
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let request = test::TestRequest::post()
+        .uri("/api/logout")
+        .cookie(cookie)
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn writes_need_login() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+    for request in [
+        test::TestRequest::post().uri("/api/universities"),
+        test::TestRequest::put().uri("/api/universities/1"),
+        test::TestRequest::patch().uri("/api/universities/1"),
+        test::TestRequest::delete().uri("/api/universities/1"),
+        test::TestRequest::post().uri("/api/profiles"),
+        test::TestRequest::put().uri("/api/profiles/1"),
+        test::TestRequest::patch().uri("/api/profiles/1"),
+        test::TestRequest::delete().uri("/api/profiles/1"),
+        test::TestRequest::post().uri("/api/logout"),
+    ] {
+        let request = request.insert_header(bearer("made up")).to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+}
+
+#[actix_web::test]
+async fn create_university() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+    let body = json!({
+        "title": "ETH Zurich",
+        "icon": "eth.png",
+        "colour": "#1F407A",
+        "longitude": 8.55,
+        "latitude": 47.38,
+    });
+
+    let request = test::TestRequest::post()
+        .uri("/api/universities")
+        .insert_header(bearer(&fixture.member))
+        .set_json(&body)
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let request = test::TestRequest::post()
+        .uri("/api/universities")
+        .insert_header(bearer(&fixture.editor))
+        .set_json(&body)
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: Value = test::read_body_json(response).await;
+    assert_eq!(created["id"], 3);
+    assert_eq!(created["title"], "ETH Zurich");
+}
+
+#[actix_web::test]
+async fn create_university_invalid() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+
+    let request = test::TestRequest::post()
+        .uri("/api/universities")
+        .insert_header(bearer(&fixture.admin))
+        .set_json(json!({
+            "title": " ",
+            "icon": "eth.png",
+            "colour": "blue",
+            "longitude": 200.0,
+            "latitude": -91.0,
+        }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: Value = test::read_body_json(response).await;
+    let fields: Vec<_> = body["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|error| error["field"].as_str().unwrap())
+        .collect();
+    assert_eq!(fields, ["title", "colour", "longitude", "latitude"]);
+
+    let request = test::TestRequest::post()
+        .uri("/api/universities")
+        .insert_header(bearer(&fixture.admin))
+        .insert_header(header::ContentType::json())
+        .set_payload("{\"title\": ")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: Value = test::read_body_json(response).await;
+    assert_eq!(body["errors"][0]["field"], "body");
+}
+
+#[actix_web::test]
+async fn change_university() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+    let body = json!({
+        "title": "Massachusetts Institute of Technology",
+        "icon": "mit.png",
+        "colour": "#A31F34",
+        "longitude": -71.09,
+        "latitude": 42.36,
+    });
+
+    let request = test::TestRequest::put()
+        .uri("/api/universities/1")
+        .insert_header(bearer(&fixture.editor))
+        .set_json(&body)
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let request = test::TestRequest::put()
+        .uri("/api/universities/1")
+        .insert_header(bearer(&fixture.admin))
+        .set_json(&body)
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let updated: Value = test::read_body_json(response).await;
+    assert_eq!(updated["title"], "Massachusetts Institute of Technology");
+
+    let request = test::TestRequest::patch()
+        .uri("/api/universities/1")
+        .insert_header(bearer(&fixture.admin))
+        .set_json(json!({"colour": "#000000"}))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let updated: Value = test::read_body_json(response).await;
+    assert_eq!(updated["colour"], "#000000");
+    assert_eq!(updated["title"], "Massachusetts Institute of Technology");
+
+    let request = test::TestRequest::patch()
+        .uri("/api/universities/9")
+        .insert_header(bearer(&fixture.admin))
+        .set_json(json!({"colour": "#000000"}))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let request = test::TestRequest::put()
+        .uri("/api/universities/9")
+        .insert_header(bearer(&fixture.admin))
+        .set_json(&body)
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn delete_university() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+
+    let request = test::TestRequest::delete()
+        .uri("/api/universities/2")
+        .insert_header(bearer(&fixture.editor))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let request = test::TestRequest::delete()
+        .uri("/api/universities/2")
+        .insert_header(bearer(&fixture.admin))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let state = AppState {
+        on_university_delete: OnUniversityDelete::Cascade,
+        ..fixture.state.clone()
+    };
+    let app = self::app(&state).await;
+    let request = test::TestRequest::delete()
+        .uri("/api/universities/2")
+        .insert_header(bearer(&fixture.admin))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(university::Entity::find_by_id(2)
+        .one(&fixture.state.db)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(profile::Entity::find_by_id(3)
+        .one(&fixture.state.db)
+        .await
+        .unwrap()
+        .is_none());
+
+    let request = test::TestRequest::delete()
+        .uri("/api/universities/2")
+        .insert_header(bearer(&fixture.admin))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn create_profile() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+    let mut body = json!({
+        "name_primary": "Dave",
+        "avatar": "dave.png",
+        "class_of": 2024,
+        "university_id": 2,
+        "qq": "123456",
+    });
+
+    let request = test::TestRequest::post()
+        .uri("/api/profiles")
+        .insert_header(bearer(&fixture.editor))
+        .set_json(&body)
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    body["class_of"] = json!(2025);
+    body["university_id"] = json!(9);
+    let request = test::TestRequest::post()
+        .uri("/api/profiles")
+        .insert_header(bearer(&fixture.editor))
+        .set_json(&body)
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let errors: Value = test::read_body_json(response).await;
+    assert_eq!(errors["errors"][0]["field"], "university_id");
+
+    body["university_id"] = json!(2);
+    body["base_id"] = json!(9);
+    let request = test::TestRequest::post()
+        .uri("/api/profiles")
+        .insert_header(bearer(&fixture.editor))
+        .set_json(&body)
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let errors: Value = test::read_body_json(response).await;
+    assert_eq!(errors["errors"][0]["field"], "base_id");
+
+    body["base_id"] = json!(2);
+    let request = test::TestRequest::post()
+        .uri("/api/profiles")
+        .insert_header(bearer(&fixture.editor))
+        .set_json(&body)
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: Value = test::read_body_json(response).await;
+    assert_eq!(created["id"], 4);
+    assert_eq!(created["base_id"], 2);
+    assert_eq!(created["qq"], "123456");
+    assert_eq!(created["contact_visibility"], "public");
+}
+
+#[actix_web::test]
+async fn change_profile() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+
+    let request = test::TestRequest::patch()
+        .uri("/api/profiles/1")
+        .insert_header(bearer(&fixture.editor))
+        .set_json(json!({"major": "Physics", "email": null}))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let updated: Value = test::read_body_json(response).await;
+    assert_eq!(updated["major"], "Physics");
+    assert!(updated.get("email").is_none());
+    assert_eq!(updated["name_primary"], "Alice");
+
+    let request = test::TestRequest::patch()
+        .uri("/api/profiles/1")
+        .insert_header(bearer(&fixture.editor))
+        .set_json(json!({"class_of": 2024}))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let request = test::TestRequest::patch()
+        .uri("/api/profiles/3")
+        .insert_header(bearer(&fixture.editor))
+        .set_json(json!({"major": "Physics"}))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let request = test::TestRequest::patch()
+        .uri("/api/profiles/1")
+        .insert_header(bearer(&fixture.editor))
+        .set_json(json!({"email": "not an address"}))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let request = test::TestRequest::put()
+        .uri("/api/profiles/3")
+        .insert_header(bearer(&fixture.admin))
+        .set_json(json!({
+            "name_primary": "Carol",
+            "avatar": "carol.png",
+            "class_of": 2024,
+            "university_id": 1,
+        }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let updated: Value = test::read_body_json(response).await;
+    assert_eq!(updated["university_id"], 1);
+    assert_eq!(updated["contact_visibility"], "public");
+
+    let request = test::TestRequest::put()
+        .uri("/api/profiles/9")
+        .insert_header(bearer(&fixture.admin))
+        .set_json(json!({
+            "name_primary": "Nobody",
+            "avatar": "nobody.png",
+            "class_of": 2024,
+            "university_id": 1,
+        }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn delete_profile() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+
+    let request = test::TestRequest::delete()
+        .uri("/api/profiles/3")
+        .insert_header(bearer(&fixture.editor))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let request = test::TestRequest::delete()
+        .uri("/api/profiles/2")
+        .insert_header(bearer(&fixture.editor))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let request = test::TestRequest::delete()
+        .uri("/api/profiles/2")
+        .insert_header(bearer(&fixture.editor))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn export() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+
+    let request = test::TestRequest::get().uri("/api/export").to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let request = test::TestRequest::get()
+        .uri("/api/export")
+        .insert_header(bearer(&fixture.editor))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let request = test::TestRequest::get()
+        .uri("/api/export?format=csv")
+        .insert_header(bearer(&fixture.admin))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = String::from_utf8(test::read_body(response).await.to_vec()).unwrap();
+    assert_eq!(body.lines().count(), 4);
+    assert!(body.contains("Carol,,Carol.png,2024,Tsinghua"));
+
+    let request = test::TestRequest::get()
+        .uri("/api/export?format=geojson")
+        .insert_header(bearer(&fixture.admin))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(body["type"], "FeatureCollection");
+    assert_eq!(body["features"][0]["properties"]["alumni_count"], 2);
+
+    let request = test::TestRequest::get()
+        .uri("/api/export?format=xml")
+        .insert_header(bearer(&fixture.admin))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn errors_are_json() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+
+    let request = test::TestRequest::get()
+        .uri("/api/v2/universities/9")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let body: Value = test::read_body_json(response).await;
+    assert_eq!(
+        body,
+        json!({"code": "not_found", "message": "No such university"})
+    );
+
+    let request = test::TestRequest::delete()
+        .uri("/api/v2/profiles/1")
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(body["code"], "unauthorized");
+
+    let request = test::TestRequest::delete()
+        .uri("/api/v2/profiles/3")
+        .insert_header(bearer(&fixture.editor))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(body["code"], "forbidden");
+
+    let request = test::TestRequest::post()
+        .uri("/api/v2/universities")
+        .insert_header(bearer(&fixture.admin))
+        .set_json(json!({"title": ""}))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(body["code"], "invalid");
+    assert_eq!(body["errors"][0]["field"], "body");
+}
+
+#[actix_web::test]
+async fn upload_avatar() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+
+    let picture = image::DynamicImage::new_rgb8(400, 300);
+    let mut png = Vec::new();
+    picture
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .unwrap();
+
+    let (content_type, body) = multipart(&png);
+    let request = test::TestRequest::post()
+        .uri("/api/v2/profiles/3/avatar")
+        .insert_header(bearer(&fixture.editor))
+        .insert_header((header::CONTENT_TYPE, content_type.clone()))
+        .set_payload(body.clone())
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let request = test::TestRequest::post()
+        .uri("/api/v2/profiles/1/avatar")
+        .insert_header(bearer(&fixture.editor))
+        .insert_header((header::CONTENT_TYPE, content_type))
+        .set_payload(body)
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let updated: Value = test::read_body_json(response).await;
+    let avatar = updated["avatar"].as_str().unwrap();
+    assert_eq!(avatar.len(), "0123456789abcdef0123456789abcdef.png".len());
+    assert_ne!(avatar, "Alice.png");
+
+    let stored = std::path::Path::new(&fixture.state.assets_root).join("avatars");
+    let large = image::open(stored.join(avatar)).unwrap();
+    assert_eq!((large.width(), large.height()), (256, 256));
+    let small = image::open(stored.join(avatar.replace(".png", "-75.png"))).unwrap();
+    assert_eq!((small.width(), small.height()), (75, 75));
+
+    let (content_type, body) = multipart(b"GIF89a, or rather not");
+    let request = test::TestRequest::post()
+        .uri("/api/v2/universities/1/icon")
+        .insert_header(bearer(&fixture.admin))
+        .insert_header((header::CONTENT_TYPE, content_type))
+        .set_payload(body)
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: Value = test::read_body_json(response).await;
+    assert_eq!(body["errors"][0]["field"], "image");
+}
+
+#[actix_web::test]
+async fn static_files() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+
+    let request = test::TestRequest::get()
+        .uri("/static/icons/MIT.png")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = test::TestRequest::get()
+        .uri("/static/icons/Missing.png")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let request = test::TestRequest::get()
+        .uri("/static/config.toml")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let request = test::TestRequest::get()
+        .uri("/no-such-file.js")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn tiles() {
+    let fixture = fixture().await;
+    let app = app(&fixture.state).await;
+    let assets_root = std::path::Path::new(&fixture.state.assets_root);
+    let source = assets_root.join("world-source.png");
+    image::DynamicImage::new_rgb8(600, 300)
+        .save(&source)
+        .unwrap();
+
+    // 600 pixels are enough for two zoom levels, the last one being 512 by 256
+    let deepest = backend::tiles::cut(&source, assets_root, None).unwrap();
+    assert_eq!(deepest, 1);
+    let tile = image::open(backend::tiles::tile_path(assets_root, 1, 1, 0)).unwrap();
+    assert_eq!((tile.width(), tile.height()), (256, 256));
+    // The picture is half as high as wide, so the first level has a transparent bottom half
+    let first = image::open(backend::tiles::tile_path(assets_root, 0, 0, 0))
+        .unwrap()
+        .to_rgba8();
+    assert_eq!(first.get_pixel(0, 0)[3], 255);
+    assert_eq!(first.get_pixel(0, 200)[3], 0);
+
+    let request = test::TestRequest::get()
+        .uri("/tiles/1/1/0.png")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    for uri in ["/tiles/1/2/0.png", "/tiles/2/0/0.png", "/tiles/1/x/0.png"] {
+        let request = test::TestRequest::get().uri(uri).to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND, "{uri}");
+    }
+}
@@ -0,0 +1,135 @@
+//! Imports files into a fresh in-memory `SQLite` database
+
+use sea_orm::{Database, DatabaseConnection, EntityTrait, PaginatorTrait};
+use std::path::Path;
+
+use backend::import::{self, Import};
+use entity::{base, profile, university};
+use migration::{Migrator, MigratorTrait};
+
+const UNIVERSITIES: &str = "\
+title,icon,colour,longitude,latitude
+MIT,MIT.png,#A31F34,-71.09,42.36
+ETH,ETH.png,not a colour,8.55,47.38
+Tsinghua,Tsinghua.png,#660874,north,40.0
+";
+
+const EVERYTHING: &str = r##"{
+  "universities": [
+    {
+      "title": "MIT",
+      "icon": "MIT.png",
+      "colour": "#A31F34",
+      "longitude": -71.09,
+      "latitude": 42.36
+    }
+  ],
+  "bases": [
+    {"title": "East Campus", "colour": "#1E66F5", "longitude": 121.5, "latitude": 31.2}
+  ],
+  "profiles": [
+    {
+      "name_primary": "Alice",
+      "avatar": "alice.png",
+      "class_of": 2025,
+      "university": "MIT",
+      "base": "East Campus"
+    },
+    {"name_primary": "Bob", "avatar": "bob.png", "class_of": 2025, "university": "MIT"}
+  ]
+}"##;
+
+async fn database() -> DatabaseConnection {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    Migrator::up(&db, None).await.unwrap();
+    db
+}
+
+/// Numbers of universities, base points and profiles in the database
+async fn counts(db: &DatabaseConnection) -> (u64, u64, u64) {
+    (
+        university::Entity::find().count(db).await.unwrap(),
+        base::Entity::find().count(db).await.unwrap(),
+        profile::Entity::find().count(db).await.unwrap(),
+    )
+}
+
+#[actix_web::test]
+async fn csv_problems_name_their_lines() {
+    let db = database().await;
+    let before = counts(&db).await;
+    let import = Import::prepare(
+        &db,
+        Path::new("universities.csv"),
+        UNIVERSITIES,
+        Some("university"),
+    )
+    .await
+    .unwrap();
+    let lines: Vec<_> = import.problems().iter().map(|each| each.line).collect();
+    assert_eq!(lines, [3, 4]);
+    assert!(import.problems()[0].message.starts_with("colour"));
+
+    assert!(import.write(&db, false).await.is_err());
+    assert_eq!(counts(&db).await, before);
+
+    let unknown = Import::prepare(&db, Path::new("universities.csv"), UNIVERSITIES, None).await;
+    assert!(unknown.is_err());
+}
+
+#[actix_web::test]
+async fn json_mixes_every_kind() {
+    let db = database().await;
+    let (universities, bases, profiles) = counts(&db).await;
+    let import = Import::prepare(&db, Path::new("classmates.json"), EVERYTHING, None)
+        .await
+        .unwrap();
+    assert!(import.problems().is_empty());
+    assert_eq!(
+        import.summary(),
+        "1 universities, 1 base points and 2 profiles"
+    );
+    import.write(&db, false).await.unwrap();
+    assert_eq!(
+        counts(&db).await,
+        (universities + 1, bases + 1, profiles + 2)
+    );
+
+    let mit = university::Entity::find().all(&db).await.unwrap();
+    let east = base::Entity::find().all(&db).await.unwrap();
+    let imported = profile::Entity::find().all(&db).await.unwrap();
+    let alice = imported
+        .iter()
+        .find(|each| each.name_primary == "Alice")
+        .unwrap();
+    assert_eq!(alice.university_id, mit.last().unwrap().id);
+    assert_eq!(alice.base_id, Some(east.last().unwrap().id));
+    let bob = imported
+        .iter()
+        .find(|each| each.name_primary == "Bob")
+        .unwrap();
+    assert_eq!(bob.base_id, None);
+
+    // Titles are taken now and there is no West Campus, each reported where its entry starts
+    let again = EVERYTHING.replace("\"base\": \"East Campus\"", "\"base\": \"West Campus\"");
+    let import = Import::prepare(&db, Path::new("classmates.json"), &again, None)
+        .await
+        .unwrap();
+    let lines: Vec<_> = import.problems().iter().map(|each| each.line).collect();
+    assert_eq!(lines, [3, 12, 15]);
+}
+
+#[actix_web::test]
+async fn dry_run_leaves_database_untouched() {
+    let db = database().await;
+    let before = counts(&db).await;
+    let file = std::env::temp_dir().join(format!("alumnimap-import-{}.json", std::process::id()));
+    std::fs::write(&file, EVERYTHING).unwrap();
+
+    import::run(&db, &file, None, true).await.unwrap();
+    assert_eq!(counts(&db).await, before);
+
+    import::run(&db, &file, None, false).await.unwrap();
+    assert_ne!(counts(&db).await, before);
+    std::fs::remove_file(&file).unwrap();
+}
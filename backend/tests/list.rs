@@ -0,0 +1,154 @@
+//! Lists a small in-memory `SQLite` database in every format
+
+use clap::Parser;
+use sea_orm::{ConnectionTrait, Database, DatabaseConnection};
+use serde_json::Value;
+use unicode_width::UnicodeWidthStr;
+
+use backend::error::AppError;
+use backend::list::{self, ListArgs};
+use migration::{Migrator, MigratorTrait};
+
+const SEED: [&str; 5] = [
+    "INSERT INTO university (id, title, icon, colour, longitude, latitude) \
+     VALUES (1, 'MIT', 'MIT.png', '#A31F34', -71.09, 42.36)",
+    "INSERT INTO university (id, title, icon, colour, longitude, latitude) \
+     VALUES (2, '清华大学', 'Tsinghua.png', '#660874', 116.33, 40.0)",
+    "INSERT INTO profile (id, name_primary, name_supplementary, avatar, class_of, university_id, major) \
+     VALUES (1, '李华', 'Li Hua', 'lihua.png', 2025, 2, '物理')",
+    "INSERT INTO profile (id, name_primary, avatar, class_of, university_id, major) \
+     VALUES (2, 'Alice', 'alice.png', 2025, 1, 'Physics, Maths')",
+    "INSERT INTO profile (id, name_primary, avatar, class_of, university_id) \
+     VALUES (3, 'Nobody', 'nobody.png', 2024, 9)",
+];
+
+/// Parses a command line the way the binary does
+#[derive(Parser)]
+struct Cli {
+    #[command(flatten)]
+    args: ListArgs,
+}
+
+/// Two universities and three profiles, the last of them going to a university that is gone
+async fn database() -> DatabaseConnection {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    Migrator::up(&db, None).await.unwrap();
+    // Only older versions could leave a profile behind
+    db.execute_unprepared("PRAGMA foreign_keys = OFF")
+        .await
+        .unwrap();
+    for each in SEED {
+        db.execute_unprepared(each).await.unwrap();
+    }
+    db
+}
+
+async fn list(db: &DatabaseConnection, args: &[&str]) -> Result<String, AppError> {
+    let args = std::iter::once("list").chain(args.iter().copied());
+    list::run(db, Cli::try_parse_from(args).unwrap().args).await
+}
+
+/// Ids of the profiles listed as JSON, in order
+async fn ids(db: &DatabaseConnection, args: &[&str]) -> Vec<i64> {
+    let args: Vec<_> = args.iter().chain(&["--format", "json"]).copied().collect();
+    let listed: Value = serde_json::from_str(&list(db, &args).await.unwrap()).unwrap();
+    listed
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|each| each["id"].as_i64().unwrap())
+        .collect()
+}
+
+#[actix_web::test]
+async fn orphans_are_shown_missing() {
+    let db = database().await;
+    assert_eq!(ids(&db, &["profile", "--orphaned"]).await, [3]);
+
+    let table = list(&db, &["profile", "--orphaned"]).await.unwrap();
+    let rows: Vec<_> = table.lines().collect();
+    assert_eq!(rows.len(), 2);
+    assert!(rows[1].contains("(missing #9)"), "{table}");
+
+    let everyone = list(&db, &["profile"]).await.unwrap();
+    assert_eq!(everyone.matches("(missing #").count(), 1);
+    assert!(list(&db, &["university", "--orphaned"]).await.is_err());
+}
+
+#[actix_web::test]
+async fn sorting_by_university() {
+    let db = database().await;
+    // The missing university comes first, then by title
+    assert_eq!(
+        ids(&db, &["profile", "--sort", "university"]).await,
+        [3, 2, 1]
+    );
+    assert_eq!(
+        ids(
+            &db,
+            &["profile", "--sort", "university", "--class-of", "2025"]
+        )
+        .await,
+        [2, 1]
+    );
+    assert!(list(&db, &["university", "--sort", "university"])
+        .await
+        .is_err());
+}
+
+#[actix_web::test]
+async fn json_and_csv() {
+    let db = database().await;
+    let json = list(
+        &db,
+        &["profile", "--format", "json", "--university", "清华大学"],
+    )
+    .await
+    .unwrap();
+    let listed: Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        listed,
+        serde_json::json!([{
+            "id": 1,
+            "name_primary": "李华",
+            "name_supplementary": "Li Hua",
+            "class_of": 2025,
+            "university_id": 2,
+            "university": "清华大学",
+            "major": "物理",
+        }])
+    );
+
+    let csv = list(&db, &["profile", "--format", "csv"]).await.unwrap();
+    let lines: Vec<_> = csv.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            "id,name_primary,name_supplementary,class_of,university_id,university,major",
+            "1,李华,Li Hua,2025,2,清华大学,物理",
+            "2,Alice,,2025,1,MIT,\"Physics, Maths\"",
+            "3,Nobody,,2024,9,,",
+        ]
+    );
+}
+
+#[actix_web::test]
+async fn wide_names_line_up() {
+    let db = database().await;
+    let table = list(&db, &["profile"]).await.unwrap();
+    let rows: Vec<_> = table.lines().collect();
+    assert_eq!(rows.len(), 4);
+    // Every class starts in the same column on screen, however wide the names before it are
+    let columns: Vec<_> = rows
+        .iter()
+        .map(|row| {
+            let start = ["CLASS OF", "2025", "2024"]
+                .iter()
+                .find_map(|class| row.find(class))
+                .unwrap();
+            row[..start].width()
+        })
+        .collect();
+    assert!(columns.iter().all(|each| *each == columns[0]), "{table}");
+    assert!(rows[1].starts_with("1   李华    Li Hua"), "{table}");
+}
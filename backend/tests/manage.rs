@@ -0,0 +1,226 @@
+//! Runs the maintenance commands against a fresh in-memory `SQLite` database
+
+use clap::Parser;
+use sea_orm::{Database, DatabaseConnection, EntityTrait};
+use serde_json::Value;
+
+use backend::cascade::OnUniversityDelete;
+use backend::error::AppError;
+use backend::manage::{self, BaseCommand, ProfileCommand};
+use entity::{profile, university};
+use migration::{Migrator, MigratorTrait};
+
+/// Parses a command line the way the binary does
+#[derive(Parser)]
+struct Cli<T: clap::Subcommand> {
+    #[command(subcommand)]
+    command: T,
+}
+
+fn parse<T: clap::Subcommand>(args: &[&str]) -> T {
+    let args = std::iter::once("alumnimap").chain(args.iter().copied());
+    Cli::<T>::try_parse_from(args).unwrap().command
+}
+
+async fn database() -> DatabaseConnection {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    Migrator::up(&db, None).await.unwrap();
+    db
+}
+
+async fn university(db: &DatabaseConnection, args: &[&str]) -> Result<String, AppError> {
+    manage::university(db, parse(args), true, OnUniversityDelete::Restrict).await
+}
+
+async fn profile(db: &DatabaseConnection, args: &[&str]) -> Result<String, AppError> {
+    manage::profile(db, parse(args), true).await
+}
+
+fn json(output: &str) -> Value {
+    serde_json::from_str(output).unwrap()
+}
+
+#[actix_web::test]
+async fn add_edit_delete() {
+    let db = database().await;
+    let added = json(
+        &university(
+            &db,
+            &[
+                "add", "--title", "MIT", "--icon", "mit.png", "--colour", "#A31F34", "--lon",
+                "-71.09", "--lat", "42.36",
+            ],
+        )
+        .await
+        .unwrap(),
+    );
+    let uni_id = added["id"].as_i64().unwrap();
+    assert_eq!(added["title"], "MIT");
+
+    let alice = json(
+        &profile(
+            &db,
+            &[
+                "add",
+                "--name",
+                "Alice",
+                "--avatar",
+                "alice.png",
+                "--class-of",
+                "2025",
+                "--university",
+                "MIT",
+                "--email",
+                "alice@example.com",
+            ],
+        )
+        .await
+        .unwrap(),
+    );
+    assert_eq!(alice["university_id"].as_i64(), Some(uni_id));
+    let id = alice["id"].to_string();
+
+    let edited = json(
+        &profile(&db, &["edit", &id, "--major", "Physics", "--email", ""])
+            .await
+            .unwrap(),
+    );
+    assert_eq!(edited["major"], "Physics");
+    assert_eq!(edited["email"], Value::Null);
+    assert_eq!(edited["name_primary"], "Alice");
+
+    let renamed = manage::university(
+        &db,
+        parse(&["edit", &uni_id.to_string(), "--title", "ETH"]),
+        false,
+        OnUniversityDelete::Restrict,
+    )
+    .await
+    .unwrap();
+    assert_eq!(renamed, format!("Updated university {uni_id}: ETH"));
+
+    // Alice still studies there
+    let restricted = university(&db, &["delete", &uni_id.to_string()]).await;
+    assert!(matches!(restricted, Err(AppError::Conflict(_))));
+
+    profile(&db, &["delete", &id]).await.unwrap();
+    assert!(profile::Entity::find().all(&db).await.unwrap().is_empty());
+    university(&db, &["delete", &uni_id.to_string()])
+        .await
+        .unwrap();
+    assert!(university::Entity::find()
+        .all(&db)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[actix_web::test]
+async fn missing_entries_are_errors() {
+    let db = database().await;
+    let cases = [
+        university(&db, &["edit", "42", "--title", "ETH"]).await,
+        university(&db, &["delete", "42"]).await,
+        profile(&db, &["edit", "42", "--major", "Physics"]).await,
+        profile(&db, &["delete", "42"]).await,
+        manage::base(
+            &db,
+            parse::<BaseCommand>(&["edit", "42", "--title", "West"]),
+            true,
+        )
+        .await,
+        manage::base(&db, parse::<BaseCommand>(&["delete", "42"]), true).await,
+    ];
+    for each in cases {
+        assert!(matches!(each, Err(AppError::NotFound(_))), "{each:?}");
+    }
+
+    // A university that does not exist is a problem with the input, not with the profile
+    let nowhere = profile(
+        &db,
+        &[
+            "add",
+            "--name",
+            "Bob",
+            "--avatar",
+            "bob.png",
+            "--class-of",
+            "2025",
+            "--university-id",
+            "42",
+        ],
+    )
+    .await;
+    assert!(matches!(nowhere, Err(AppError::Invalid(_))));
+}
+
+#[actix_web::test]
+async fn json_output() {
+    let db = database().await;
+    let added = json(
+        &manage::base(
+            &db,
+            parse::<BaseCommand>(&[
+                "add",
+                "--title",
+                "East Campus",
+                "--colour",
+                "#1E66F5",
+                "--lon",
+                "121.5",
+                "--lat",
+                "31.2",
+            ]),
+            true,
+        )
+        .await
+        .unwrap(),
+    );
+    let fields: Vec<_> = added.as_object().unwrap().keys().cloned().collect();
+    assert_eq!(fields, ["colour", "id", "latitude", "longitude", "title"]);
+    let id = added["id"].as_i64().unwrap();
+
+    let deleted = json(
+        &manage::base(
+            &db,
+            parse::<BaseCommand>(&["delete", &id.to_string()]),
+            true,
+        )
+        .await
+        .unwrap(),
+    );
+    assert_eq!(deleted, serde_json::json!({"deleted": id, "profiles": 0}));
+
+    // Deleting a profile leaves nothing else behind, so there is no count
+    university(
+        &db,
+        &[
+            "add", "--title", "MIT", "--icon", "mit.png", "--colour", "#A31F34", "--lon", "0",
+            "--lat", "0",
+        ],
+    )
+    .await
+    .unwrap();
+    let added = json(
+        &manage::profile(
+            &db,
+            parse::<ProfileCommand>(&[
+                "add",
+                "--name",
+                "Carol",
+                "--avatar",
+                "carol.png",
+                "--class-of",
+                "2024",
+                "--university",
+                "MIT",
+            ]),
+            true,
+        )
+        .await
+        .unwrap(),
+    );
+    let id = added["id"].as_i64().unwrap();
+    let deleted = json(&profile(&db, &["delete", &id.to_string()]).await.unwrap());
+    assert_eq!(deleted, serde_json::json!({"deleted": id}));
+}
@@ -0,0 +1,63 @@
+//! Cuts pictures into tiles in a temporary assets directory
+
+use std::path::PathBuf;
+
+use backend::tiles::{self, MAX_ZOOM};
+use entity::map::TILE_SIZE;
+
+/// A fresh assets directory for a single test
+fn assets_root(name: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("alumnimap-tiles-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    root
+}
+
+#[test]
+fn sharpest_zoom_stays_within_the_picture() {
+    for width in [1, 255, 256, 511, 512, 600, 1024, 5000, 65536, 1_000_000] {
+        let zoom = tiles::sharpest_zoom(width);
+        assert!(zoom == 0 || TILE_SIZE << zoom <= width, "{width}");
+        assert!(
+            zoom == MAX_ZOOM || TILE_SIZE << (zoom + 1) > width,
+            "{width}"
+        );
+    }
+}
+
+#[test]
+fn cut_writes_every_tile() {
+    let root = assets_root("every");
+    let source = root.join("world.png");
+    let (width, height) = (700_u32, 300_u32);
+    image::DynamicImage::new_rgb8(width, height)
+        .save(&source)
+        .unwrap();
+
+    let deepest = tiles::cut(&source, &root, Some(3)).unwrap();
+    assert_eq!(deepest, 3);
+    for zoom in 0..=deepest {
+        let rows = (height << zoom).div_ceil(width);
+        assert_eq!(tiles::tile_count(width, height, zoom), (1 << zoom, rows));
+        let written = std::fs::read_dir(root.join("tiles").join(zoom.to_string()))
+            .unwrap()
+            .map(|column| std::fs::read_dir(column.unwrap().path()).unwrap().count())
+            .sum::<usize>();
+        assert_eq!(written, (1 << zoom) * rows as usize, "zoom {zoom}");
+
+        // The last row holds what is left of the picture, then transparency
+        let level_height = (height * (TILE_SIZE << zoom)).div_ceil(width);
+        let filled = level_height - (rows - 1) * TILE_SIZE;
+        let last = image::open(tiles::tile_path(&root, zoom, 0, rows - 1))
+            .unwrap()
+            .to_rgba8();
+        assert_eq!(last.dimensions(), (TILE_SIZE, TILE_SIZE));
+        assert_eq!(last.get_pixel(0, filled - 1)[3], 255, "zoom {zoom}");
+        if filled < TILE_SIZE {
+            assert_eq!(last.get_pixel(0, filled)[3], 0, "zoom {zoom}");
+        }
+    }
+    assert!(!tiles::tile_path(&root, 3, 8, 0).exists());
+    assert!(!tiles::tile_path(&root, 4, 0, 0).exists());
+    std::fs::remove_dir_all(&root).unwrap();
+}
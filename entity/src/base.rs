@@ -0,0 +1,41 @@
+//! Base points, the schools or campuses alumni set out from
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A school or campus alumni set out from, drawn as the origin of their lines
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "base")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub title: String,
+    pub colour: String,
+    #[sea_orm(column_type = "Float")]
+    pub longitude: f32,
+    #[sea_orm(column_type = "Float")]
+    pub latitude: f32,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::profile::Entity")]
+    Profile,
+}
+
+impl Related<super::profile::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Profile.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+/// A base point together with the universities its alumni went to, as served by `/api/bases`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WithDestinations {
+    #[serde(flatten)]
+    pub base: Model,
+    /// Ids of the universities to draw a line to
+    pub universities: Vec<i32>,
+}
@@ -1,2 +1,7 @@
+pub mod account;
+pub mod base;
+pub mod map;
 pub mod profile;
+pub mod search;
+pub mod session;
 pub mod university;
@@ -0,0 +1,61 @@
+//! How the picture under the map is drawn, as served by `/api/map`
+
+use serde::{Deserialize, Serialize};
+
+/// Edge of a tile cut by `alumnimap tiles`, in pixels
+pub const TILE_SIZE: u32 = 256;
+
+/// How the picture maps longitude and latitude to its pixels
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Projection {
+    /// Longitude and latitude are spaced evenly, the picture is twice as wide as high
+    #[default]
+    Equirectangular,
+    /// As used by most web maps, cut off at 85.05° north and south
+    WebMercator,
+    /// A compromise that bends the meridians, interpolated from Robinson's table every 5°
+    Robinson,
+}
+
+/// The picture under the map and how to line points up with it
+///
+/// Longitude and latitude are offset first, wrapping around, then projected, scaled and offset
+/// again by the final offsets. Every offset is in degrees. The defaults fit the built-in picture.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MapConfig {
+    pub projection: Projection,
+    /// Picture in the assets directory to use instead of the built-in one
+    pub image: Option<String>,
+    /// Deepest zoom level cut by `alumnimap tiles`, drawn over the picture when zoomed in
+    pub tile_zoom: Option<u32>,
+    /// How far lines bend to the side of the great circle, as a fraction of their length
+    pub line_curvature: f32,
+    /// Draws lines as dashes flying from the base point to the destination
+    pub line_dashes: bool,
+    pub longitude_offset: f32,
+    pub latitude_offset: f32,
+    pub longitude_scale: f32,
+    pub latitude_scale: f32,
+    pub final_longitude_offset: f32,
+    pub final_latitude_offset: f32,
+}
+
+impl Default for MapConfig {
+    fn default() -> Self {
+        Self {
+            projection: Projection::Equirectangular,
+            image: None,
+            tile_zoom: None,
+            line_curvature: 0.0,
+            line_dashes: false,
+            longitude_offset: -150.0,
+            latitude_offset: 0.0,
+            longitude_scale: 0.985,
+            latitude_scale: 1.0,
+            final_longitude_offset: 0.0,
+            final_latitude_offset: 0.0,
+        }
+    }
+}
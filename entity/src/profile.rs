@@ -13,12 +13,42 @@ pub struct Model {
     pub avatar: String,
     pub class_of: i32,
     pub university_id: i32,
+    /// Base point the alumnus set out from, the default one if missing
+    pub base_id: Option<i32>,
     pub major: Option<String>,
     pub bio: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub qq: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub wechat: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub matrix: Option<String>,
+    pub contact_visibility: Visibility,
+    /// Set by the server when some contacts were left out for the requester
+    #[sea_orm(ignore)]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub contacts_hidden: bool,
+}
+
+/// Who may see the contacts of a profile
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(16))")]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    /// Everyone
+    #[default]
+    #[sea_orm(string_value = "public")]
+    Public,
+    /// Anyone logged in from the same class, along with those who may see hidden contacts
+    #[sea_orm(string_value = "classmates")]
+    Classmates,
+    /// Only admins and the editor of the class
+    #[sea_orm(string_value = "hidden")]
+    Hidden,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -26,9 +56,38 @@ pub enum Relation {
     #[sea_orm(
         belongs_to = "super::university::Entity",
         from = "Column::UniversityId",
-        to = "super::university::Column::Id"
+        to = "super::university::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Restrict"
     )]
     University,
+    #[sea_orm(
+        belongs_to = "super::base::Entity",
+        from = "Column::BaseId",
+        to = "super::base::Column::Id",
+        on_update = "Cascade",
+        on_delete = "SetNull"
+    )]
+    Base,
+}
+
+impl Related<super::base::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Base.def()
+    }
+}
+
+impl Related<super::university::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::University.def()
+    }
 }
 
 impl ActiveModelBehavior for ActiveModel {}
+
+/// How many alumni graduated in a year
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClassSize {
+    pub class_of: i32,
+    pub alumni: i64,
+}
@@ -0,0 +1,51 @@
+//! Results of `/api/search`, shared by the backend and the frontend
+
+use serde::{Deserialize, Serialize};
+
+use crate::{profile, university};
+
+/// Something that matched a search, tagged with its `kind`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Hit {
+    /// A university whose title matched
+    University(university::Model),
+    /// A profile, with the title of the university it went to
+    Profile {
+        #[serde(flatten)]
+        profile: profile::Model,
+        university: String,
+        matched: Field,
+    },
+}
+
+/// The field of a profile that matched
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Field {
+    NamePrimary,
+    NameSupplementary,
+    Major,
+    Bio,
+}
+
+impl Field {
+    /// Every searched field, in the order they are checked
+    pub const ALL: [Field; 4] = [
+        Field::NamePrimary,
+        Field::NameSupplementary,
+        Field::Major,
+        Field::Bio,
+    ];
+
+    /// The text of this field in a profile, empty if it has none
+    #[must_use]
+    pub fn of(self, profile: &profile::Model) -> &str {
+        match self {
+            Field::NamePrimary => &profile.name_primary,
+            Field::NameSupplementary => profile.name_supplementary.as_deref().unwrap_or_default(),
+            Field::Major => profile.major.as_deref().unwrap_or_default(),
+            Field::Bio => profile.bio.as_deref().unwrap_or_default(),
+        }
+    }
+}
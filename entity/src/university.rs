@@ -18,6 +18,23 @@ pub struct Model {
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(has_many = "super::profile::Entity")]
+    Profile,
+}
+
+impl Related<super::profile::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Profile.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
+
+/// A university with how many alumni went there
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WithAlumni {
+    #[serde(flatten)]
+    pub university: Model,
+    pub alumni: i64,
+}
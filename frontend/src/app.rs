@@ -1,21 +1,48 @@
 use std::sync::LazyLock;
+use std::time::Duration;
 
+use crate::fetcher::FetchedData;
 use crate::init;
+use crate::link::Link;
 use crate::widgets::map::WorldMap;
 use crate::widgets::search::Search;
+use entity::profile;
 
 pub static APP_URL: LazyLock<String> = LazyLock::new(get_app_url);
+/// The page without its query or fragment, which may hold a [`Link`]
+#[cfg(target_arch = "wasm32")]
 fn get_app_url() -> String {
-    web_sys::window()
+    let href = web_sys::window()
         .expect("Cannot get current window object, do your browser support it?")
         .location()
         .href()
-        .expect("Cannot get current href, do your browser support it?")
+        .expect("Cannot get current href, do your browser support it?");
+    href.split(['?', '#'])
+        .next()
+        .unwrap_or_default()
+        .to_string()
 }
 
+/// Outside the browser there is no page, such as in tests
+#[cfg(not(target_arch = "wasm32"))]
+fn get_app_url() -> String {
+    String::new()
+}
+
+/// The address bar is updated at most this often, in seconds, as browsers limit it
+const LINK_INTERVAL: f64 = 0.5;
+
 pub struct AlumniMapApp {
     world_map: WorldMap,
     search: Search,
+    /// Graduating classes to pick from
+    years: FetchedData<Vec<profile::ClassSize>>,
+    /// The picked class, everyone if none
+    class_of: Option<i32>,
+    /// Fragment last put in or read from the address bar
+    fragment: String,
+    /// When the fragment was last put in the address bar
+    fragment_written_at: f64,
 }
 
 impl AlumniMapApp {
@@ -24,17 +51,88 @@ impl AlumniMapApp {
         egui_extras::install_image_loaders(&cc.egui_ctx);
         init::init_font(&cc.egui_ctx);
         init::set_theme(&cc.egui_ctx);
-        Self {
+        let years = FetchedData::new(format!("{}api/v2/years", *APP_URL), |response| {
+            response.json::<Vec<profile::ClassSize>>().ok()
+        });
+        let mut app = Self {
             world_map: WorldMap::new(),
             search: Search::new(),
+            years,
+            class_of: None,
+            fragment: String::new(),
+            fragment_written_at: 0.0,
+        };
+        app.follow_link(Link::current_fragment());
+        app
+    }
+
+    /// Shows what the fragment of a link describes
+    fn follow_link(&mut self, fragment: String) {
+        let link = Link::parse(&fragment);
+        self.class_of = link.class_of;
+        self.world_map.restore(&link);
+        self.fragment = fragment;
+    }
+
+    /// Follows links pasted into the address bar, and keeps it pointing at what is shown
+    fn sync_link(&mut self, ctx: &egui::Context) {
+        let current = Link::current_fragment();
+        if current != self.fragment {
+            self.follow_link(current);
+            return;
+        }
+        if !self.world_map.link_restored() {
+            return;
+        }
+        let shown = self.world_map.link().to_fragment();
+        if shown == self.fragment {
+            return;
+        }
+        let waited = ctx.input(|input| input.time) - self.fragment_written_at;
+        if waited < LINK_INTERVAL {
+            ctx.request_repaint_after(Duration::from_secs_f64(LINK_INTERVAL - waited));
+            return;
         }
+        Link::replace_fragment(&shown);
+        self.fragment = shown;
+        self.fragment_written_at = ctx.input(|input| input.time);
+    }
+
+    /// Lets the user pick a graduating class, so each class can see its own map
+    fn render_year_picker(&mut self, ui: &mut egui::Ui) {
+        self.years.poll(ui.ctx());
+        let Some(years) = &self.years.data else {
+            return;
+        };
+        let selected_text = self
+            .class_of
+            .map_or_else(|| "All".to_string(), |class_of| class_of.to_string());
+        egui::ComboBox::from_label("Class of")
+            .selected_text(selected_text)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut self.class_of, None, "All");
+                for each in years {
+                    ui.selectable_value(
+                        &mut self.class_of,
+                        Some(each.class_of),
+                        format!("{} ({})", each.class_of, each.alumni),
+                    );
+                }
+            });
+        self.world_map.set_class_of(self.class_of);
     }
 }
 
 impl eframe::App for AlumniMapApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.world_map.render_docked_lists(ctx);
         egui::CentralPanel::default().show(ctx, |ui| {
             self.search.render(ui, &mut self.world_map);
+            ui.horizontal(|ui| {
+                self.render_year_picker(ui);
+                self.world_map.render_cohort_selector(ui);
+                self.world_map.render_list_controls(ui);
+            });
             ui.separator();
             self.world_map.render(ui);
 
@@ -43,6 +141,7 @@ impl eframe::App for AlumniMapApp {
                 egui::warn_if_debug_build(ui);
             });
         });
+        self.sync_link(ctx);
     }
 }
 
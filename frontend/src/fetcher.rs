@@ -3,6 +3,7 @@
 //!
 //! This is a one-time fetch, if fails it won't try again, once finished it'll be useless.
 
+use std::fmt::Write;
 use std::sync::{Arc, Mutex};
 
 /// Wrapper over the fetched data
@@ -65,3 +66,31 @@ impl<T> FetchedData<T> {
         false
     }
 }
+
+/// Appends the given parameters to the URL as a query string, leaving out those without a value
+pub fn with_query(mut url: String, params: &[(&str, Option<i32>)]) -> String {
+    let mut separator = '?';
+    for (name, value) in params {
+        if let Some(value) = value {
+            url.push(separator);
+            url.push_str(name);
+            url.push('=');
+            url.push_str(&value.to_string());
+            separator = '&';
+        }
+    }
+    url
+}
+
+/// Percent-encodes text to be put in a URL, leaving only unreserved characters as they are
+pub fn encode_component(text: &str) -> String {
+    let mut encoded = String::new();
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            encoded.push(char::from(byte));
+        } else {
+            let _ = write!(encoded, "%{byte:02X}");
+        }
+    }
+    encoded
+}
@@ -1,6 +1,7 @@
 mod app;
 mod fetcher;
 mod init;
+mod link;
 mod widgets;
 
 pub use app::AlumniMapApp;
@@ -0,0 +1,194 @@
+//! Deep links into the map, kept in the fragment of the URL
+//!
+//! A link like `#year=2025&base=2&uni=12&profile=34&lon=116.33&lat=40.00&zoom=5.0` picks the
+//! class and base point shown, the list that is open and the profile it is scrolled to, and where
+//! the camera is. Every part is optional.
+//!
+//! Only reading and writing the address bar needs a browser, the rest also builds natively so it
+//! can be tested.
+
+#[cfg(target_arch = "wasm32")]
+use eframe::wasm_bindgen::JsValue;
+#[cfg(target_arch = "wasm32")]
+use std::fmt::Write;
+
+/// What a link restores
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Link {
+    pub class_of: Option<i32>,
+    pub cohort: Option<i32>,
+    /// University whose list is open
+    pub uni_id: Option<i32>,
+    /// Profile that list is scrolled to
+    pub profile_id: Option<i32>,
+    /// Longitude, latitude and zoom of the camera
+    pub camera: Option<(f32, f32, f32)>,
+}
+
+impl Link {
+    /// Reads a fragment, with or without its leading `#`, skipping whatever it does not understand
+    ///
+    /// Values may be percent-encoded, as some apps do to links they pass on.
+    pub fn parse(fragment: &str) -> Self {
+        let mut link = Self::default();
+        let (mut longitude, mut latitude, mut zoom) = (None, None, None);
+        for pair in fragment.trim_start_matches('#').split('&') {
+            let Some((key, value)) = pair.split_once('=') else {
+                continue;
+            };
+            let Some(value) = percent_decode(value) else {
+                continue;
+            };
+            let value = value.as_str();
+            match key {
+                "year" => link.class_of = value.parse().ok(),
+                "base" => link.cohort = value.parse().ok(),
+                "uni" => link.uni_id = value.parse().ok(),
+                "profile" => link.profile_id = value.parse().ok(),
+                "lon" => longitude = value.parse().ok(),
+                "lat" => latitude = value.parse().ok(),
+                "zoom" => zoom = value.parse().ok(),
+                _ => (),
+            }
+        }
+        if let (Some(longitude), Some(latitude)) = (longitude, latitude) {
+            link.camera = Some((longitude, latitude, zoom.unwrap_or(1.0)));
+        }
+        link
+    }
+
+    /// Writes the link as a fragment without the leading `#`, empty if there is nothing to restore
+    pub fn to_fragment(&self) -> String {
+        let mut parts = Vec::new();
+        for (key, value) in [
+            ("year", self.class_of),
+            ("base", self.cohort),
+            ("uni", self.uni_id),
+            ("profile", self.profile_id),
+        ] {
+            if let Some(value) = value {
+                parts.push(format!("{key}={value}"));
+            }
+        }
+        if let Some((longitude, latitude, zoom)) = self.camera {
+            parts.push(format!(
+                "lon={longitude:.2}&lat={latitude:.2}&zoom={zoom:.1}"
+            ));
+        }
+        parts.join("&")
+    }
+
+    /// The fragment of the current page, without the leading `#`
+    #[cfg(target_arch = "wasm32")]
+    pub fn current_fragment() -> String {
+        web_sys::window()
+            .and_then(|window| window.location().hash().ok())
+            .map(|hash| hash.trim_start_matches('#').to_string())
+            .unwrap_or_default()
+    }
+
+    /// Outside the browser there is no address bar to read
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn current_fragment() -> String {
+        String::new()
+    }
+
+    /// Puts the fragment in the address bar, without adding to the history
+    #[cfg(target_arch = "wasm32")]
+    pub fn replace_fragment(fragment: &str) {
+        let Some(window) = web_sys::window() else {
+            return;
+        };
+        let location = window.location();
+        let mut url = location.pathname().unwrap_or_default();
+        url.push_str(&location.search().unwrap_or_default());
+        if !fragment.is_empty() {
+            let _ = write!(url, "#{fragment}");
+        }
+        if let Ok(history) = window.history() {
+            let _ = history.replace_state_with_url(&JsValue::NULL, "", Some(&url));
+        }
+    }
+
+    /// Outside the browser there is no address bar to write
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn replace_fragment(_fragment: &str) {}
+}
+
+/// Decodes `%XX` escapes, or gives up if they are broken or do not make up UTF-8
+fn percent_decode(value: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value.as_bytes();
+    while let Some((&first, tail)) = rest.split_first() {
+        if first == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(first);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let link = Link {
+            class_of: Some(2025),
+            cohort: Some(2),
+            uni_id: Some(12),
+            profile_id: Some(34),
+            camera: Some((116.33, -40.0, 5.0)),
+        };
+        let fragment = link.to_fragment();
+        assert_eq!(
+            fragment,
+            "year=2025&base=2&uni=12&profile=34&lon=116.33&lat=-40.00&zoom=5.0"
+        );
+        assert_eq!(Link::parse(&format!("#{fragment}")), link);
+        assert_eq!(Link::parse(&fragment), link);
+
+        let nothing = Link::default();
+        assert_eq!(nothing.to_fragment(), "");
+        assert_eq!(Link::parse(""), nothing);
+    }
+
+    #[test]
+    fn unknown_keys_are_skipped() {
+        let link = Link::parse("#utm_source=chat&uni=12&&theme&profile=34=35");
+        assert_eq!(
+            link,
+            Link {
+                uni_id: Some(12),
+                ..Link::default()
+            }
+        );
+    }
+
+    #[test]
+    fn bad_numbers_are_skipped() {
+        let link = Link::parse("year=twenty&uni=12.5&profile=&lon=116.33&lat=north&zoom=5");
+        assert_eq!(link, Link::default());
+
+        // A camera needs both coordinates, the zoom may be missing or broken
+        let link = Link::parse("lon=116.33&lat=40&zoom=close");
+        assert_eq!(link.camera, Some((116.33, 40.0, 1.0)));
+    }
+
+    #[test]
+    fn percent_encoded_values() {
+        let link = Link::parse("year=%32025&lon=%2D116.33&lat=40%2e5");
+        assert_eq!(link.class_of, Some(2025));
+        assert_eq!(link.camera, Some((-116.33, 40.5, 1.0)));
+
+        // Broken escapes are skipped like any other bad value
+        assert_eq!(Link::parse("uni=%1&profile=%zz4").uni_id, None);
+        assert_eq!(percent_decode("%E5%8C%97"), Some("北".to_string()));
+        assert_eq!(percent_decode("%FF"), None);
+    }
+}
@@ -1,5 +1,6 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+#[cfg(target_arch = "wasm32")]
 fn main() {
     use eframe::wasm_bindgen::JsCast as _;
 
@@ -31,7 +32,7 @@ fn main() {
         // Remove the loading text and spinner:
         if let Some(loading_text) = document.get_element_by_id("loading_text") {
             match start_result {
-                Ok(_) => {
+                Ok(()) => {
                     loading_text.remove();
                 }
                 Err(e) => {
@@ -44,3 +45,9 @@ fn main() {
         }
     });
 }
+
+/// Only the library builds natively, so that it can be tested
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    eprintln!("AlumniMap runs in the browser, build it with trunk");
+}
@@ -0,0 +1,75 @@
+//! Moves the visible part of the map without the user dragging it
+//!
+//! Zoom is relative to the whole map, so a zoom of 2 shows half of its width.
+
+use egui::{Pos2, Rect};
+
+use crate::widgets::projection::Calibrated;
+
+/// How long an animated flight takes, in seconds
+const FLIGHT_DURATION: f64 = 0.8;
+pub const MIN_ZOOM: f32 = 0.5;
+pub const MAX_ZOOM: f32 = 30.0;
+/// Zoom used when showing a single university
+pub const FOCUS_ZOOM: f32 = 5.0;
+
+/// A move of the camera towards a point on the map
+pub struct Flight {
+    /// Longitude and latitude to centre on, only projected when stepped as the projection may
+    /// still be loading when the flight starts
+    target: (f32, f32),
+    zoom: f32,
+    animated: bool,
+    /// Where the camera was and when the flight started, known once it is first stepped
+    from: Option<(Rect, f64)>,
+}
+
+impl Flight {
+    pub fn new(target: (f32, f32), zoom: f32, animated: bool) -> Self {
+        Self {
+            target,
+            zoom: zoom.clamp(MIN_ZOOM, MAX_ZOOM),
+            animated,
+            from: None,
+        }
+    }
+
+    /// Gives the visible area for this frame, and whether the flight has landed
+    ///
+    /// `map_area` is where the whole map is drawn in the scene.
+    pub fn step(
+        &mut self,
+        current: Rect,
+        map_area: Rect,
+        projection: &Calibrated,
+        now: f64,
+    ) -> (Rect, bool) {
+        let target = projection.to_norm(self.target.0, self.target.1);
+        let centre = Pos2::new(
+            map_area.left() + target.x * map_area.width(),
+            map_area.top() + target.y * map_area.height(),
+        );
+        let destination = Rect::from_center_size(centre, map_area.size() / self.zoom);
+        // Nothing was shown yet, so there is nowhere to fly from
+        if !self.animated || current == Rect::ZERO {
+            return (destination, true);
+        }
+        let (from, started) = *self.from.get_or_insert((current, now));
+        let progress = ((now - started) / FLIGHT_DURATION).clamp(0.0, 1.0);
+        if progress >= 1.0 {
+            return (destination, true);
+        }
+        #[allow(clippy::cast_possible_truncation)]
+        let eased = ease(progress as f32);
+        let area = Rect::from_min_max(
+            from.min.lerp(destination.min, eased),
+            from.max.lerp(destination.max, eased),
+        );
+        (area, false)
+    }
+}
+
+/// Starts and ends slowly, moving fastest halfway
+fn ease(progress: f32) -> f32 {
+    progress * progress * (3.0 - 2.0 * progress)
+}
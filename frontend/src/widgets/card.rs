@@ -2,7 +2,7 @@
 
 use crate::app::APP_URL;
 use egui::Vec2;
-use entity::profile;
+use entity::profile::{self, Visibility};
 
 /// Moves a value out from the struct
 fn extract_string(from: Option<&String>) -> &str {
@@ -12,9 +12,9 @@ fn extract_string(from: Option<&String>) -> &str {
         ""
     }
 }
-/// Calls egui to draw everything to the screen
-pub fn render(model: &profile::Model, ui: &mut egui::Ui) {
-    ui.horizontal(|ui| {
+/// Calls egui to draw everything to the screen, returning the response of the card
+pub fn render(model: &profile::Model, ui: &mut egui::Ui) -> egui::Response {
+    let response = ui.horizontal(|ui| {
         let image = egui::Image::new(format!("{}static/avatars/{}", *APP_URL, model.avatar))
             .fit_to_exact_size(Vec2::new(75.0, 75.0));
         ui.add(image);
@@ -39,6 +39,7 @@ pub fn render(model: &profile::Model, ui: &mut egui::Ui) {
         });
     });
     ui.separator();
+    response.response
 }
 
 /// Draws the collapsible contacts section
@@ -61,5 +62,12 @@ fn render_contacts(model: &profile::Model, ui: &mut egui::Ui) {
                     ui.label(format!("󰘨 {text}"));
                 }
             });
+            if model.contacts_hidden {
+                let reason = match model.contact_visibility {
+                    Visibility::Classmates => "Only visible to logged in classmates",
+                    _ => "Only visible to the editors of this class",
+                };
+                ui.label("󰌾 Some contacts are hidden").on_hover_text(reason);
+            }
         });
 }
@@ -0,0 +1,141 @@
+//! Groups points that would be drawn on top of each other
+//!
+//! The distance is in scene units, and since markers keep their size on screen it shrinks as the
+//! map is zoomed in, splitting clusters apart.
+
+use egui::Pos2;
+
+/// Points that are close enough to be drawn as one
+pub struct Cluster {
+    /// Mean position of the members
+    pub centre: Pos2,
+    /// Indices of the points in the cluster, the first one being where it started
+    pub members: Vec<usize>,
+}
+
+/// Clusters points, each joining the first cluster whose first member is closer than `distance`
+///
+/// Points are taken in order, so earlier points should be the more important ones.
+pub fn cluster(points: &[Pos2], distance: f32) -> Vec<Cluster> {
+    let mut clusters: Vec<Cluster> = Vec::new();
+    for (index, point) in points.iter().enumerate() {
+        let joined = clusters
+            .iter_mut()
+            .find(|each| points[each.members[0]].distance(*point) < distance);
+        match joined {
+            Some(each) => each.members.push(index),
+            None => clusters.push(Cluster {
+                centre: *point,
+                members: vec![index],
+            }),
+        }
+    }
+    for each in &mut clusters {
+        #[allow(clippy::cast_precision_loss)]
+        let count = each.members.len() as f32;
+        let sum = each.members.iter().fold(egui::Vec2::ZERO, |sum, &index| {
+            sum + points[index].to_vec2()
+        });
+        each.centre = (sum / count).to_pos2();
+    }
+    clusters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Members of each cluster as points, in an order that does not depend on the input
+    fn groups(points: &[Pos2], clusters: &[Cluster]) -> Vec<Vec<(i32, i32)>> {
+        #[allow(clippy::cast_possible_truncation)]
+        let mut groups: Vec<Vec<_>> = clusters
+            .iter()
+            .map(|each| {
+                let mut group: Vec<_> = each
+                    .members
+                    .iter()
+                    .map(|&index| (points[index].x as i32, points[index].y as i32))
+                    .collect();
+                group.sort_unstable();
+                group
+            })
+            .collect();
+        groups.sort();
+        groups
+    }
+
+    /// Two tight groups far apart and a point on its own
+    fn scattered() -> Vec<Pos2> {
+        vec![
+            Pos2::new(0.0, 0.0),
+            Pos2::new(100.0, 100.0),
+            Pos2::new(2.0, 1.0),
+            Pos2::new(-300.0, 50.0),
+            Pos2::new(101.0, 98.0),
+            Pos2::new(1.0, 3.0),
+        ]
+    }
+
+    #[test]
+    fn close_points_merge() {
+        let points = scattered();
+        let clusters = cluster(&points, 10.0);
+        assert_eq!(clusters.len(), 3);
+
+        let mut members: Vec<_> = clusters
+            .iter()
+            .flat_map(|each| each.members.iter().copied())
+            .collect();
+        members.sort_unstable();
+        assert_eq!(members, (0..points.len()).collect::<Vec<_>>());
+
+        let first = &clusters[0];
+        assert_eq!(first.members, [0, 2, 5]);
+        assert!(first.centre.distance(Pos2::new(1.0, 4.0 / 3.0)) < 1e-4);
+    }
+
+    #[test]
+    fn distant_points_stay_apart() {
+        let points = scattered();
+        let clusters = cluster(&points, 1.0);
+        assert_eq!(clusters.len(), points.len());
+        for (index, each) in clusters.iter().enumerate() {
+            assert_eq!(each.members, [index]);
+            assert_eq!(each.centre, points[index]);
+        }
+        assert!(cluster(&[], 10.0).is_empty());
+    }
+
+    #[test]
+    fn order_does_not_matter() {
+        let points = scattered();
+        let expected = groups(&points, &cluster(&points, 10.0));
+        for shift in 1..points.len() {
+            let mut shuffled = points.clone();
+            shuffled.rotate_left(shift);
+            assert_eq!(groups(&shuffled, &cluster(&shuffled, 10.0)), expected);
+            shuffled.reverse();
+            assert_eq!(groups(&shuffled, &cluster(&shuffled, 10.0)), expected);
+        }
+    }
+
+    #[test]
+    fn zooming_in_splits_clusters() {
+        let points = [
+            Pos2::new(0.0, 0.0),
+            Pos2::new(3.0, 0.0),
+            Pos2::new(0.0, 12.0),
+        ];
+        let sizes = |distance| {
+            let mut sizes: Vec<_> = cluster(&points, distance)
+                .iter()
+                .map(|each| each.members.len())
+                .collect();
+            sizes.sort_unstable();
+            sizes
+        };
+        assert_eq!(sizes(20.0), [3]);
+        assert_eq!(sizes(5.0), [1, 2]);
+        assert_eq!(sizes(2.0), [1, 1, 1]);
+    }
+}
@@ -0,0 +1,178 @@
+//! Paths along the surface of the globe
+//!
+//! Points are longitude and latitude in degrees. Paths are sampled finely enough to look smooth
+//! once projected, and are cut where they cross the edge of the map by
+//! [`Calibrated::to_norm_path`](super::projection::Calibrated::to_norm_path).
+
+use std::f32::consts::PI;
+
+/// Degrees between samples of a path
+const STEP: f32 = 2.0;
+
+type Vec3 = [f32; 3];
+
+/// Samples the shortest way from one point to another
+///
+/// `curvature` bends the path to the left of the way, seen from `from`, so that its middle is off
+/// the great circle by that fraction of the length. Negative values bend it to the right.
+pub fn great_circle(from: (f32, f32), to: (f32, f32), curvature: f32) -> Vec<(f32, f32)> {
+    let start = to_vector(from);
+    let end = to_vector(to);
+    let angle = dot(start, end).clamp(-1.0, 1.0).acos();
+    if angle < f32::EPSILON {
+        return vec![from, to];
+    }
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let steps = ((angle.to_degrees() / STEP).ceil() as usize).max(1);
+    let heading = heading(start, end, angle);
+    // Pole of the great circle, left of the way
+    let pole = cross(start, heading);
+    let mut path = Vec::with_capacity(steps + 1);
+    path.push(from);
+    for step in 1..steps {
+        #[allow(clippy::cast_precision_loss)]
+        let t = step as f32 / steps as f32;
+        let mut point = add(
+            scale(start, (t * angle).cos()),
+            scale(heading, (t * angle).sin()),
+        );
+        if curvature != 0.0 {
+            let offset = (curvature * angle * (PI * t).sin()).tan();
+            point = normalise(add(point, scale(pole, offset))).unwrap_or(point);
+        }
+        path.push(to_lon_lat(point));
+    }
+    path.push(to);
+    path
+}
+
+/// Direction to head from `start` towards `end`, at right angles to `start`
+fn heading(start: Vec3, end: Vec3, angle: f32) -> Vec3 {
+    normalise(add(end, scale(start, -angle.cos())))
+        // Antipodes have no single shortest way, any will do
+        .or_else(|| normalise(cross(start, [0.0, 0.0, 1.0])))
+        .unwrap_or([1.0, 0.0, 0.0])
+}
+
+fn to_vector((longitude, latitude): (f32, f32)) -> Vec3 {
+    let (longitude, latitude) = (longitude.to_radians(), latitude.to_radians());
+    [
+        latitude.cos() * longitude.cos(),
+        latitude.cos() * longitude.sin(),
+        latitude.sin(),
+    ]
+}
+
+fn to_lon_lat([x, y, z]: Vec3) -> (f32, f32) {
+    (
+        y.atan2(x).to_degrees(),
+        z.clamp(-1.0, 1.0).asin().to_degrees(),
+    )
+}
+
+fn dot(a: Vec3, b: Vec3) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn add(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn scale(a: Vec3, factor: f32) -> Vec3 {
+    [a[0] * factor, a[1] * factor, a[2] * factor]
+}
+
+/// Scales to unit length, unless too short to have a direction
+fn normalise(a: Vec3) -> Option<Vec3> {
+    let length = dot(a, a).sqrt();
+    (length > 1e-6).then(|| scale(a, 1.0 / length))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::widgets::projection::Calibrated;
+    use entity::map::MapConfig;
+
+    /// Degrees between two points along the globe
+    fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+        dot(to_vector(a), to_vector(b))
+            .clamp(-1.0, 1.0)
+            .acos()
+            .to_degrees()
+    }
+
+    /// Checks the path joins the points in small, even steps
+    fn assert_smooth(path: &[(f32, f32)], from: (f32, f32), to: (f32, f32)) {
+        assert_eq!(path.first(), Some(&from));
+        assert_eq!(path.last(), Some(&to));
+        let total: f32 = path.windows(2).map(|pair| distance(pair[0], pair[1])).sum();
+        assert!((total - distance(from, to)).abs() < 0.1, "{path:?}");
+        for pair in path.windows(2) {
+            assert!(distance(pair[0], pair[1]) <= STEP + 0.01, "{pair:?}");
+        }
+    }
+
+    #[test]
+    fn identical_points() {
+        for curvature in [0.0, 0.2] {
+            let path = great_circle((116.33, 40.0), (116.33, 40.0), curvature);
+            assert_eq!(path, [(116.33, 40.0), (116.33, 40.0)]);
+        }
+    }
+
+    #[test]
+    fn antipodal_points() {
+        for (from, to) in [((0.0, 0.0), (180.0, 0.0)), ((30.0, 90.0), (30.0, -90.0))] {
+            let path = great_circle(from, to, 0.0);
+            assert!(path.len() > 90, "{path:?}");
+            assert!(path
+                .iter()
+                .all(|(longitude, latitude)| longitude.is_finite() && latitude.is_finite()));
+            assert_smooth(&path, from, to);
+        }
+    }
+
+    #[test]
+    fn crossing_the_antimeridian() {
+        let calibrated = Calibrated::new(MapConfig {
+            longitude_offset: 0.0,
+            ..MapConfig::default()
+        });
+        let path = great_circle((170.0, 10.0), (-170.0, 20.0), 0.0);
+        // The short way, not around the world
+        assert!(path.len() < 15, "{path:?}");
+        assert_smooth(&path, (170.0, 10.0), (-170.0, 20.0));
+
+        let pieces = calibrated.to_norm_path(&path);
+        assert_eq!(pieces.len(), 2, "{pieces:?}");
+        for piece in &pieces {
+            assert!(piece.len() >= 2);
+            let (left, right) = piece
+                .iter()
+                .fold((f32::MAX, f32::MIN), |(left, right), point| {
+                    (left.min(point.x), right.max(point.x))
+                });
+            assert!(right - left < 0.1, "{piece:?}");
+        }
+        // Leaves at the east edge and comes back at the west one, at the same height
+        let leaving = pieces[0].last().unwrap();
+        let arriving = pieces[1].first().unwrap();
+        assert!(
+            leaving.x > 0.98 && arriving.x < 0.02,
+            "{leaving:?} {arriving:?}"
+        );
+        assert!((leaving.y - arriving.y).abs() < 1e-4);
+
+        let inland = great_circle((-0.13, 51.5), (116.33, 40.0), 0.1);
+        assert_eq!(calibrated.to_norm_path(&inland).len(), 1);
+    }
+}
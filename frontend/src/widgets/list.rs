@@ -1,12 +1,16 @@
 //! Pops up a list when any point is clicked
 
-use egui::Pos2;
+use egui::{Pos2, Vec2};
 
 use crate::app::APP_URL;
-use crate::fetcher::FetchedData;
+use crate::fetcher::{self, FetchedData};
+use crate::link::Link;
 use crate::widgets::card;
 use entity::profile;
 
+/// Tallest a docked list gets before it scrolls
+const DOCKED_HEIGHT: f32 = 400.0;
+
 /// A list contains many profile cards
 pub struct List {
     profiles: FetchedData<Vec<profile::Model>>,
@@ -15,39 +19,70 @@ pub struct List {
 }
 /// Keep track of the [`List`]'s state
 pub struct ListState {
+    /// Set by [`Popups`](super::popups::Popups), tells windows of the same university apart
+    pub id: u64,
     pub open: bool,
+    /// Where the list was asked for
+    pub anchor: Pos2,
     initial_pos: Option<Pos2>,
+    /// Profile the list was opened for, scrolled to once it is shown
+    pub profile_id: Option<i32>,
+    scrolled: bool,
+    /// Whether the user asked to show where the university is on the map
+    pub locate: bool,
     pub inner: List,
 }
 
 /// Data manipulation
 impl ListState {
-    /// Creates a new list
-    pub fn new(title: String, uni_id: i32, initial_pos: Pos2) -> Self {
-        let profiles = FetchedData::new(format!("{}api/profiles/{uni_id}", *APP_URL), |response| {
-            let str: String = response.json().unwrap_or_default();
-            serde_json::from_str::<Vec<profile::Model>>(&str).ok()
-        });
+    /// Creates a new list, only holding profiles from the given base point and class if any
+    pub fn new(
+        title: String,
+        uni_id: i32,
+        cohort: Option<i32>,
+        class_of: Option<i32>,
+        initial_pos: Pos2,
+    ) -> Self {
+        let url = fetcher::with_query(
+            format!("{}api/v2/profiles/{uni_id}", *APP_URL),
+            &[("base_id", cohort), ("class_of", class_of)],
+        );
+        let profiles =
+            FetchedData::new(url, |response| response.json::<Vec<profile::Model>>().ok());
         let list = List {
             profiles,
             title,
             uni_id,
         };
         Self {
+            id: 0,
             open: true,
+            anchor: initial_pos,
             initial_pos: Some(initial_pos),
+            profile_id: None,
+            scrolled: false,
+            locate: false,
             inner: list,
         }
     }
+
+    /// Moves the window away from where it was asked for, before it is shown
+    pub fn cascade(&mut self, offset: Vec2) {
+        if let Some(pos) = &mut self.initial_pos {
+            *pos += offset;
+        }
+    }
 }
 
 /// Graphics
 impl ListState {
-    /// Calls egui to draw everything to the screen
+    /// Calls egui to draw everything to the screen, in a window of its own
     pub fn render(&mut self, ctx: &egui::Context) {
+        let mut open = self.open;
         let mut window = egui::Window::new(&self.inner.title)
+            .id(egui::Id::new(("list", self.id)))
             .collapsible(true)
-            .open(&mut self.open);
+            .open(&mut open);
 
         // Move to new position when reopened
         if let Some(pos) = self.initial_pos.take() {
@@ -55,15 +90,65 @@ impl ListState {
         }
 
         window.show(ctx, |ui| {
-            egui::ScrollArea::vertical().show(ui, |ui| {
-                if let Some(data) = &self.inner.profiles.data {
-                    for each in data {
-                        card::render(each, ui);
+            self.render_toolbar(ui);
+            egui::ScrollArea::vertical().show(ui, |ui| self.render_cards(ui));
+        });
+        self.open &= open;
+
+        self.inner.profiles.poll(ctx);
+    }
+
+    /// Calls egui to draw everything into a panel shared with other lists
+    pub fn render_docked(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new(&self.inner.title)
+            .id_salt(("list", self.id))
+            .default_open(true)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    self.render_toolbar(ui);
+                    if ui.button("󰅖").on_hover_text("Close").clicked() {
+                        self.open = false;
                     }
-                }
+                });
+                egui::ScrollArea::vertical()
+                    .id_salt(("list_cards", self.id))
+                    .max_height(DOCKED_HEIGHT)
+                    .show(ui, |ui| self.render_cards(ui));
             });
+
+        self.inner.profiles.poll(ui.ctx());
+    }
+
+    fn render_toolbar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.button("󰆤").on_hover_text("Show on the map").clicked() {
+                self.locate = true;
+            }
+            if ui
+                .button("󰌷")
+                .on_hover_text("Copy a link to this list")
+                .clicked()
+            {
+                let link = Link {
+                    uni_id: Some(self.inner.uni_id),
+                    profile_id: self.profile_id,
+                    ..Link::default()
+                };
+                ui.ctx()
+                    .copy_text(format!("{}#{}", *APP_URL, link.to_fragment()));
+            }
         });
+    }
 
-        self.inner.profiles.poll(ctx);
+    fn render_cards(&mut self, ui: &mut egui::Ui) {
+        if let Some(data) = &self.inner.profiles.data {
+            for each in data {
+                let response = card::render(each, ui);
+                if !self.scrolled && self.profile_id == Some(each.id) {
+                    response.scroll_to_me(Some(egui::Align::Center));
+                    self.scrolled = true;
+                }
+            }
+        }
     }
 }
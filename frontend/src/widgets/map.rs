@@ -1,298 +1,787 @@
 //! Draw points and lines on a world map
 
-use egui::{Color32, Pos2, Rect};
+use egui::{Color32, Pos2, Rect, Vec2};
 
 use crate::app::APP_URL;
-use crate::fetcher::FetchedData;
+use crate::fetcher::{self, FetchedData};
+use crate::link::Link;
+use crate::widgets::camera::{self, Flight};
+use crate::widgets::cluster::{self, Cluster};
+use crate::widgets::geodesic;
 use crate::widgets::list::ListState;
-use entity::university;
-
-/// Defines the world map image to use
-///
-/// The map offset value will be added to the provided value in order to translate the map if it's not
-/// centered at the 0 degree longitude.
-///
-/// The scale value is used to scale the transformed coordinates by some value to fit on the map
-///
-/// The final offset will be added after the transformation
-const MAP_LONGITUDE_OFFSET: f32 = -150.0;
-const MAP_LATITUDE_OFFSET: f32 = 0.0;
-const LONGITUDE_SCALE: f32 = 0.985;
-const LATITUDE_SCALE: f32 = 1.0;
-const FINAL_LONGITUDE_OFFSET: f32 = 0.0;
-const FINAL_LATITUDE_OFFSET: f32 = 0.0;
+use crate::widgets::popups::Popups;
+use crate::widgets::projection::Calibrated;
+use entity::map::{MapConfig, TILE_SIZE};
+use entity::{base, university};
+
+/// Destination points closer than this are drawn as one cluster, in the units of marker sizes
+const CLUSTER_DISTANCE: f32 = 80.0;
+const CLUSTER_RADIUS: f32 = 50.0;
+/// How much clicking a cluster zooms in
+const CLUSTER_ZOOM_STEP: f32 = 2.5;
+/// Dashes of lines, in the units of line widths
+const DASH_LENGTH: f32 = 12.0;
+const DASH_GAP: f32 = 8.0;
+/// How fast dashes fly along their lines each second
+const DASH_SPEED: f32 = 40.0;
+/// Used unless the backend names another picture, [`MapConfig::default`] is lined up with it
 const IMAGE: egui::ImageSource<'_> = egui::include_image!("../../assets/world.png");
 
+/// A list asked for by a link, which can only be opened once its university is known
+struct PendingList {
+    uni_id: i32,
+    profile_id: Option<i32>,
+    /// Whether to fly there, unless the link already placed the camera
+    fly: bool,
+}
+
 /// The world map on the main interface
 pub struct WorldMap {
-    /// All dests will draw a line from the base point
-    base: FetchedData<university::Model>,
-    dests: FetchedData<Vec<university::Model>>,
+    /// Each dest draws a line from the base points its alumni set out from
+    bases: FetchedData<Vec<base::WithDestinations>>,
+    /// Each with how many alumni went there
+    dests: FetchedData<Vec<university::WithAlumni>>,
+    /// Picture under the map and how to line points up with it
+    map_config: FetchedData<MapConfig>,
+    projection: Calibrated,
     internal_area: Rect,
+    /// Where the map image was drawn in the scene last frame
+    map_area: Rect,
+    /// Where the camera is moving on its own, if anywhere
+    flight: Option<Flight>,
+    /// Whether the camera left where it started, so that links should keep it
+    camera_moved: bool,
+    /// List to open from a link once the dests are fetched
+    pending_list: Option<PendingList>,
+    /// Only show this base point and its dests, all if none
+    cohort: Option<i32>,
+    /// Only show dests and lines of this graduating class, all if none
+    class_of: Option<i32>,
 
-    /// All currently visible [`ListState`]s
-    popups: Vec<ListState>,
-    /// Points that should be highlighted
-    pub highlights: Vec<university::Model>,
+    /// Lists of the universities clicked
+    popups: Popups,
+    /// Ids of the points that should be highlighted
+    pub highlights: Vec<i32>,
 }
 
 /// Data manipulation
 impl WorldMap {
     /// Creates a new world map
     pub fn new() -> Self {
-        let base = FetchedData::new(format!("{}api/base", *APP_URL), |response| {
-            let str: String = response.json().unwrap_or_default();
-            serde_json::from_str::<university::Model>(&str).ok()
-        });
-        let dests = FetchedData::new(format!("{}api/universities", *APP_URL), |response| {
-            let str: String = response.json().unwrap_or_default();
-            serde_json::from_str::<Vec<university::Model>>(&str).ok()
-        });
         Self {
-            base,
-            dests,
+            bases: fetch_bases(None),
+            dests: fetch_dests(None),
+            map_config: FetchedData::new(format!("{}api/v2/map", *APP_URL), |response| {
+                response.json::<MapConfig>().ok()
+            }),
+            projection: Calibrated::default(),
             internal_area: Rect::ZERO,
-            popups: Vec::new(),
+            map_area: Rect::ZERO,
+            flight: None,
+            camera_moved: false,
+            pending_list: None,
+            cohort: None,
+            class_of: None,
+            popups: Popups::new(),
             highlights: Vec::new(),
         }
     }
+
+    /// Shows only the given graduating class, or everyone
+    pub fn set_class_of(&mut self, class_of: Option<i32>) {
+        if self.class_of == class_of {
+            return;
+        }
+        self.class_of = class_of;
+        self.bases = fetch_bases(class_of);
+        self.dests = fetch_dests(class_of);
+        // Open lists were fetched for the previous class
+        self.popups.clear();
+    }
+
+    /// Shows only the given base point and its dests, or all of them
+    pub fn set_cohort(&mut self, cohort: Option<i32>) {
+        if self.cohort == cohort {
+            return;
+        }
+        self.cohort = cohort;
+        // Open lists were fetched for the previous cohort
+        self.popups.clear();
+    }
+
+    /// Describes what is shown, so it can be restored from a link
+    pub fn link(&self) -> Link {
+        let open = self.popups.latest();
+        Link {
+            class_of: self.class_of,
+            cohort: self.cohort,
+            uni_id: open.map(|each| each.inner.uni_id),
+            profile_id: open.and_then(|each| each.profile_id),
+            camera: self.camera(),
+        }
+    }
+
+    /// Whether everything a link asked for is shown, so that [`Self::link`] describes it
+    pub fn link_restored(&self) -> bool {
+        // The camera of a link only lands once the map and its projection are loaded
+        self.pending_list.is_none() && self.flight.is_none()
+    }
+
+    /// Shows what a link describes
+    pub fn restore(&mut self, link: &Link) {
+        self.set_class_of(link.class_of);
+        self.set_cohort(link.cohort);
+        self.popups.clear();
+        if let Some((longitude, latitude, zoom)) = link.camera {
+            self.jump_to(longitude, latitude, zoom);
+        }
+        self.pending_list = link.uni_id.map(|uni_id| PendingList {
+            uni_id,
+            profile_id: link.profile_id,
+            fly: link.camera.is_none(),
+        });
+    }
+
+    /// Longitude, latitude and zoom of the camera, once it has been moved
+    fn camera(&self) -> Option<(f32, f32, f32)> {
+        if !self.camera_moved || self.map_area == Rect::ZERO || self.internal_area == Rect::ZERO {
+            return None;
+        }
+        let centre = self.internal_area.center();
+        let norm = Pos2::new(
+            (centre.x - self.map_area.left()) / self.map_area.width(),
+            (centre.y - self.map_area.top()) / self.map_area.height(),
+        );
+        let (longitude, latitude) = self.projection.to_lon_lat(norm);
+        let zoom = self.map_area.width() / self.internal_area.width();
+        Some((longitude, latitude, zoom))
+    }
+
+    /// A university shown on the map, if it is there
+    pub fn find_university(&self, uni_id: i32) -> Option<&university::Model> {
+        self.dests
+            .data
+            .as_ref()?
+            .iter()
+            .map(|dest| &dest.university)
+            .find(|each| each.id == uni_id)
+    }
+
+    /// Smoothly moves the camera to centre on the given point with the given zoom
+    pub fn fly_to(&mut self, longitude: f32, latitude: f32, zoom: f32) {
+        self.flight = Some(Flight::new((longitude, latitude), zoom, true));
+    }
+
+    /// Same as [`Self::fly_to`], without the animation
+    pub fn jump_to(&mut self, longitude: f32, latitude: f32, zoom: f32) {
+        self.flight = Some(Flight::new((longitude, latitude), zoom, false));
+    }
+
+    /// Flies to a university shown on the map, returning whether it is there
+    pub fn fly_to_university(&mut self, uni_id: i32) -> bool {
+        let Some(found) = self.find_university(uni_id) else {
+            return false;
+        };
+        let (longitude, latitude) = (found.longitude, found.latitude);
+        self.fly_to(longitude, latitude, camera::FOCUS_ZOOM);
+        true
+    }
+
+    /// Opens the list of a university unless it is open already
+    pub fn open_university(&mut self, university: &university::Model, pos: Pos2) {
+        if self.popups.contains(university.id) {
+            return;
+        }
+        let popup = ListState::new(
+            university.title.clone(),
+            university.id,
+            self.cohort,
+            self.class_of,
+            pos,
+        );
+        self.popups.open(popup);
+    }
+
+    /// Opens the list of a university with every alumnus in it, scrolled to the given profile
+    ///
+    /// A list of the same university that is already open is replaced, as it may have been
+    /// narrowed down to a class or base point the profile is not part of.
+    pub fn open_profile(&mut self, title: String, uni_id: i32, profile_id: i32, pos: Pos2) {
+        self.popups.retain(|list| list.inner.uni_id != uni_id);
+        let mut popup = ListState::new(title, uni_id, None, None, pos);
+        popup.profile_id = Some(profile_id);
+        self.popups.open(popup);
+    }
 }
 
 /// Render related
 impl WorldMap {
     /// Calls egui to draw everything to the screen
     pub fn render(&mut self, ui: &mut egui::Ui) {
+        self.step_flight(ui.ctx());
+
         // Map itself
+        let view = ui.available_rect_before_wrap();
         let mut real_internal_area = self.internal_area;
-        let scene = egui::Scene::new().zoom_range(0.5..=30.0);
-        scene.show(ui, &mut real_internal_area, |ui| {
-            let image = egui::Image::new(IMAGE)
+        let scene = egui::Scene::new().zoom_range(camera::MIN_ZOOM..=camera::MAX_ZOOM);
+        let scene_res = scene.show(ui, &mut real_internal_area, |ui| {
+            let image = match &self.projection.config().image {
+                Some(image) => egui::Image::new(format!("{}static/{image}", *APP_URL)),
+                None => egui::Image::new(IMAGE),
+            };
+            let image = image
                 .sense(egui::Sense::CLICK | egui::Sense::HOVER)
                 .fit_to_original_size(1.0);
             let image_res = ui.add(image);
             let area = image_res.rect;
+            self.map_area = area;
+            self.draw_tiles(ui, area, view.width());
+            let markers = self.markers(area);
             self.draw_base_and_lines(ui, area);
-            self.draw_points(ui, area);
-            if let Some(click_pos) = image_res.interact_pointer_pos() {
-                self.check_click(ui, click_pos, area);
+            self.draw_points(ui, area, &markers);
+            if image_res.clicked() {
+                if let Some(click_pos) = image_res.interact_pointer_pos() {
+                    self.check_click(ui, click_pos, area, &markers);
+                }
             }
             if let Some(hover_pos) = image_res.hover_pos() {
-                self.check_hover(ui, hover_pos, area);
+                self.check_hover(ui, hover_pos, area, &markers);
             }
         });
         self.internal_area = real_internal_area;
+        // The user takes over the camera by dragging or zooming
+        let zoomed = scene_res.response.hovered()
+            && ui.input(|input| {
+                (input.zoom_delta() - 1.0).abs() > f32::EPSILON
+                    || input.smooth_scroll_delta != Vec2::ZERO
+            });
+        if scene_res.response.dragged() || zoomed {
+            self.flight = None;
+            self.camera_moved = true;
+        }
 
         // Popups
-        let mut closing = Vec::new();
-        for (index, each) in self.popups.iter_mut().enumerate() {
-            each.render(ui.ctx());
-            if !each.open {
-                closing.push(index);
-            }
-        }
-        for each in closing {
-            self.popups.remove(each);
+        if let Some(uni_id) = self.popups.render_floating(ui.ctx()) {
+            self.fly_to_university(uni_id);
         }
 
-        self.base.poll(ui.ctx());
+        self.map_config.poll(ui.ctx());
+        if let Some(config) = self.map_config.data.take() {
+            self.projection = Calibrated::new(config);
+            // The picture may change, so it is drawn again before anything is placed on it
+            self.map_area = Rect::ZERO;
+        }
+        self.bases.poll(ui.ctx());
         self.dests.poll(ui.ctx());
+        self.open_pending_list(ui.ctx());
     }
 
-    /// Draws the base point and all lines from the base to the dests
+    /// Opens the list a link asked for, once the dests are there to find its university
+    fn open_pending_list(&mut self, ctx: &egui::Context) {
+        if !self.dests.done {
+            return;
+        }
+        let Some(pending) = self.pending_list.take() else {
+            return;
+        };
+        let Some(found) = self.find_university(pending.uni_id).cloned() else {
+            return;
+        };
+        let pos = ctx.screen_rect().center();
+        match pending.profile_id {
+            Some(profile_id) => self.open_profile(found.title.clone(), found.id, profile_id, pos),
+            None => self.open_university(&found, pos),
+        }
+        if pending.fly {
+            self.fly_to(found.longitude, found.latitude, camera::FOCUS_ZOOM);
+        }
+    }
+
+    /// Moves the camera one frame further along its flight
+    fn step_flight(&mut self, ctx: &egui::Context) {
+        // The map has to be drawn with its own picture and projection to know where to go
+        if !self.map_config.done || self.map_area == Rect::ZERO {
+            return;
+        }
+        let Some(flight) = &mut self.flight else {
+            return;
+        };
+        let now = ctx.input(|input| input.time);
+        let (area, landed) = flight.step(self.internal_area, self.map_area, &self.projection, now);
+        self.internal_area = area;
+        self.camera_moved = true;
+        if landed {
+            self.flight = None;
+        } else {
+            ctx.request_repaint();
+        }
+    }
+
+    /// Lets the user pick which base point to show, only when there is more than one
+    pub fn render_cohort_selector(&mut self, ui: &mut egui::Ui) {
+        let Some(bases) = &self.bases.data else {
+            return;
+        };
+        if bases.len() < 2 {
+            return;
+        }
+        let selected_text = self
+            .cohort
+            .and_then(|id| bases.iter().find(|each| each.base.id == id))
+            .map_or("All", |each| each.base.title.as_str());
+        let mut cohort = self.cohort;
+        egui::ComboBox::from_label("Cohort")
+            .selected_text(selected_text)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut cohort, None, "All");
+                for each in bases {
+                    ui.selectable_value(&mut cohort, Some(each.base.id), &each.base.title);
+                }
+            });
+        self.set_cohort(cohort);
+    }
+
+    /// Calls egui to draw the lists docked beside the map, before the map itself
+    pub fn render_docked_lists(&mut self, ctx: &egui::Context) {
+        if let Some(uni_id) = self.popups.render_docked(ctx) {
+            self.fly_to_university(uni_id);
+        }
+    }
+
+    /// Lets the user dock the lists and close them all
+    pub fn render_list_controls(&mut self, ui: &mut egui::Ui) {
+        self.popups.render_controls(ui);
+    }
+
+    /// Base points of the current cohort
+    fn shown_bases(&self) -> impl Iterator<Item = &base::WithDestinations> {
+        self.bases
+            .data
+            .iter()
+            .flatten()
+            .filter(|each| self.cohort.is_none_or(|id| each.base.id == id))
+    }
+
+    /// Whether a dest is reached from any base point of the current cohort
+    fn is_shown(&self, dest: &university::WithAlumni) -> bool {
+        self.cohort.is_none()
+            || self
+                .shown_bases()
+                .any(|each| each.universities.contains(&dest.university.id))
+    }
+
+    /// Draws the base points and the lines from each of them to their dests
     fn draw_base_and_lines(&self, ui: &egui::Ui, area: Rect) {
-        if let Some(data) = &self.base.data {
-            let painter = ui.painter();
-            let base_pos = to_ui_coords(to_norm_coords(data.longitude, data.latitude), area);
+        let painter = ui.painter();
+        // Keeps sizes the same on screen however far the map is zoomed in
+        let on_screen = self.internal_area.height() / area.height();
+        let config = self.projection.config();
+        #[allow(clippy::cast_possible_truncation)]
+        let dash_offset = if config.line_dashes {
+            ui.ctx().request_repaint();
+            ui.input(|input| input.time) as f32 * DASH_SPEED * on_screen
+        } else {
+            0.0
+        };
+        for origin in self.shown_bases() {
+            let base = (origin.base.longitude, origin.base.latitude);
             if let Some(data) = &self.dests.data {
-                for each in data {
-                    let dest_pos =
-                        to_ui_coords(to_norm_coords(each.longitude, each.latitude), area);
-                    painter.line_segment(
-                        [base_pos, dest_pos],
-                        egui::Stroke::new(
-                            5.0 / area.height() * self.internal_area.height(),
-                            Color32::from_hex(&each.colour).unwrap_or_default(),
-                        ),
+                for each in data
+                    .iter()
+                    .filter(|each| origin.universities.contains(&each.university.id))
+                {
+                    let dest = &each.university;
+                    let path = geodesic::great_circle(
+                        base,
+                        (dest.longitude, dest.latitude),
+                        config.line_curvature,
                     );
+                    let stroke = egui::Stroke::new(
+                        line_width(each.alumni) * on_screen,
+                        Color32::from_hex(&dest.colour).unwrap_or_default(),
+                    );
+                    let mut travelled = 0.0;
+                    for piece in self.projection.to_norm_path(&path) {
+                        let points: Vec<Pos2> = piece
+                            .into_iter()
+                            .map(|norm| to_ui_coords(norm, area))
+                            .collect();
+                        if config.line_dashes {
+                            let (dash, gap) = (DASH_LENGTH * on_screen, DASH_GAP * on_screen);
+                            painter.extend(egui::Shape::dashed_line_with_offset(
+                                &points,
+                                stroke,
+                                &[dash],
+                                &[gap],
+                                (dash_offset - travelled).rem_euclid(dash + gap),
+                            ));
+                            travelled += points
+                                .windows(2)
+                                .map(|pair| pair[0].distance(pair[1]))
+                                .sum::<f32>();
+                        } else {
+                            painter.line(points, stroke);
+                        }
+                    }
                 }
             }
+        }
+        // Drawn last so no line covers a base point
+        for origin in self.shown_bases() {
+            let base_pos = to_ui_coords(
+                self.projection
+                    .to_norm(origin.base.longitude, origin.base.latitude),
+                area,
+            );
             painter.circle(
                 base_pos,
                 20.0,
-                Color32::from_hex(&data.colour).unwrap_or_default(),
+                Color32::from_hex(&origin.base.colour).unwrap_or_default(),
                 egui::Stroke::NONE,
             );
         }
     }
 
-    /// Recursively draws all destination points
-    fn draw_points(&self, ui: &egui::Ui, area: Rect) {
+    /// Draws the tiles of the visible part of the map over the picture, at the level that fits the
+    /// zoom
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    fn draw_tiles(&self, ui: &egui::Ui, area: Rect, view_width: f32) {
+        let Some(tile_zoom) = self.projection.config().tile_zoom else {
+            return;
+        };
+        let visible = self.internal_area.intersect(area);
+        if !visible.is_positive() {
+            return;
+        }
+        // Width of the whole map on screen, in pixels
+        let on_screen =
+            area.width() * view_width / self.internal_area.width() * ui.ctx().pixels_per_point();
+        let level = (on_screen / TILE_SIZE as f32)
+            .log2()
+            .ceil()
+            .clamp(0.0, tile_zoom as f32) as u32;
+        let edge = area.width() / (1_u32 << level) as f32;
+        let first = ((visible.min - area.min) / edge).floor();
+        let last = ((visible.max - area.min) / edge).ceil();
+        for x in first.x as u32..last.x as u32 {
+            for y in first.y as u32..last.y as u32 {
+                let rect = Rect::from_min_size(
+                    area.min + Vec2::new(x as f32, y as f32) * edge,
+                    Vec2::splat(edge),
+                );
+                egui::Image::new(format!("{}tiles/{level}/{x}/{y}.png", *APP_URL))
+                    .paint_at(ui, rect);
+            }
+        }
+    }
+
+    /// Draws every marker, a single destination point or a cluster of them
+    fn draw_points(&self, ui: &egui::Ui, area: Rect, markers: &[Cluster]) {
+        let Some(data) = &self.dests.data else {
+            return;
+        };
+        for marker in markers {
+            if let [index] = marker.members[..] {
+                self.draw_point(ui, area, &data[index]);
+            } else {
+                self.draw_cluster(ui.painter(), area, marker);
+            }
+        }
+    }
+
+    /// Draws a single destination point with its icon
+    fn draw_point(&self, ui: &egui::Ui, area: Rect, dest: &university::WithAlumni) {
         let painter = ui.painter();
-        if let Some(data) = &self.dests.data {
-            for each in data {
-                let draw_pos = to_ui_coords(to_norm_coords(each.longitude, each.latitude), area);
-                if self.highlights.contains(each) {
-                    let left_top = Pos2::new(
-                        draw_pos.x - (60.0 / area.height() * self.internal_area.height()),
-                        draw_pos.y - (60.0 / area.height() * self.internal_area.height()),
-                    );
-                    let right_bottom = Pos2::new(
-                        draw_pos.x + (60.0 / area.height() * self.internal_area.height()),
-                        draw_pos.y + (60.0 / area.height() * self.internal_area.height()),
-                    );
-                    let rect = Rect {
-                        min: left_top,
-                        max: right_bottom,
-                    };
-                    painter.circle(
-                        draw_pos,
-                        80.0 / area.height() * self.internal_area.height(),
-                        Color32::WHITE,
-                        egui::Stroke::new(
-                            10.0 / area.height() * self.internal_area.height(),
-                            Color32::LIGHT_RED,
-                        ),
-                    );
-                    egui::Image::new(format!("{}static/icons/{}", *APP_URL, each.icon))
-                        .paint_at(ui, rect);
+        let each = &dest.university;
+        let draw_pos = to_ui_coords(self.projection.to_norm(each.longitude, each.latitude), area);
+        let highlighted = self.highlights.contains(&each.id);
+        if highlighted {
+            let left_top = Pos2::new(
+                draw_pos.x - (60.0 / area.height() * self.internal_area.height()),
+                draw_pos.y - (60.0 / area.height() * self.internal_area.height()),
+            );
+            let right_bottom = Pos2::new(
+                draw_pos.x + (60.0 / area.height() * self.internal_area.height()),
+                draw_pos.y + (60.0 / area.height() * self.internal_area.height()),
+            );
+            let rect = Rect {
+                min: left_top,
+                max: right_bottom,
+            };
+            painter.circle(
+                draw_pos,
+                80.0 / area.height() * self.internal_area.height(),
+                Color32::WHITE,
+                egui::Stroke::new(
+                    10.0 / area.height() * self.internal_area.height(),
+                    Color32::LIGHT_RED,
+                ),
+            );
+            egui::Image::new(format!("{}static/icons/{}", *APP_URL, each.icon)).paint_at(ui, rect);
+        } else {
+            let left_top = Pos2::new(
+                draw_pos.x - (40.0 / area.height() * self.internal_area.height()),
+                draw_pos.y - (40.0 / area.height() * self.internal_area.height()),
+            );
+            let right_bottom = Pos2::new(
+                draw_pos.x + (40.0 / area.height() * self.internal_area.height()),
+                draw_pos.y + (40.0 / area.height() * self.internal_area.height()),
+            );
+            let rect = Rect {
+                min: left_top,
+                max: right_bottom,
+            };
+            painter.circle(
+                draw_pos,
+                40.0 / area.height() * self.internal_area.height(),
+                Color32::WHITE,
+                egui::Stroke::new(
+                    2.0 / area.height() * self.internal_area.height(),
+                    Color32::LIGHT_GRAY,
+                ),
+            );
+            egui::Image::new(format!("{}static/icons/{}", *APP_URL, each.icon)).paint_at(ui, rect);
+        }
+        let marker_radius = if highlighted { 80.0 } else { 40.0 };
+        self.draw_badge(painter, draw_pos, marker_radius, dest.alumni, area);
+    }
+
+    /// Draws a bubble with how many destination points it holds
+    fn draw_cluster(&self, painter: &egui::Painter, area: Rect, marker: &Cluster) {
+        let scale = self.internal_area.height() / area.height();
+        let highlighted = self.dests.data.as_ref().is_some_and(|data| {
+            marker
+                .members
+                .iter()
+                .any(|&index| self.highlights.contains(&data[index].university.id))
+        });
+        let stroke = if highlighted {
+            egui::Stroke::new(10.0 * scale, Color32::LIGHT_RED)
+        } else {
+            egui::Stroke::new(4.0 * scale, Color32::WHITE)
+        };
+        painter.circle(
+            marker.centre,
+            CLUSTER_RADIUS * scale,
+            Color32::from_rgb(30, 102, 245),
+            stroke,
+        );
+        painter.text(
+            marker.centre,
+            egui::Align2::CENTER_CENTER,
+            marker.members.len().to_string(),
+            egui::FontId::proportional(36.0 * scale),
+            Color32::WHITE,
+        );
+    }
+
+    /// Draws the number of alumni on the upper right of a destination point
+    fn draw_badge(
+        &self,
+        painter: &egui::Painter,
+        draw_pos: Pos2,
+        marker_radius: f32,
+        alumni: i64,
+        area: Rect,
+    ) {
+        let scale = self.internal_area.height() / area.height();
+        let offset = marker_radius * std::f32::consts::FRAC_1_SQRT_2 * scale;
+        let badge_pos = Pos2::new(draw_pos.x + offset, draw_pos.y - offset);
+        painter.circle(
+            badge_pos,
+            20.0 * scale,
+            Color32::from_rgb(210, 15, 57),
+            egui::Stroke::new(2.0 * scale, Color32::WHITE),
+        );
+        painter.text(
+            badge_pos,
+            egui::Align2::CENTER_CENTER,
+            alumni.to_string(),
+            egui::FontId::proportional(24.0 * scale),
+            Color32::WHITE,
+        );
+    }
+
+    /// Handles the logic when a marker is clicked
+    ///
+    /// A destination point opens its list, while a cluster is zoomed into until it splits up.
+    fn check_click(&mut self, ui: &egui::Ui, click_pos: Pos2, area: Rect, markers: &[Cluster]) {
+        let Some(marker) = self.marker_at(click_pos, area, markers) else {
+            return;
+        };
+        let Some(data) = &self.dests.data else {
+            return;
+        };
+        if let [index] = marker.members[..] {
+            let clicked = data[index].university.clone();
+            let initial_pos = ui
+                .input(|input| input.pointer.interact_pos())
+                .unwrap_or_default();
+            self.open_university(&clicked, initial_pos);
+            return;
+        }
+        let norm = Pos2::new(
+            (marker.centre.x - area.left()) / area.width(),
+            (marker.centre.y - area.top()) / area.height(),
+        );
+        let (longitude, latitude) = self.projection.to_lon_lat(norm);
+        self.fly_to(longitude, latitude, self.zoom() * CLUSTER_ZOOM_STEP);
+    }
+
+    /// The marker under the given point, the closest one if they overlap
+    fn marker_at<'a>(&self, pos: Pos2, area: Rect, markers: &'a [Cluster]) -> Option<&'a Cluster> {
+        let scale = self.internal_area.height() / area.height();
+        markers
+            .iter()
+            .map(|marker| (marker, marker.centre.distance(pos)))
+            .filter(|(marker, distance)| {
+                let radius = if marker.members.len() == 1 {
+                    40.0
                 } else {
-                    let left_top = Pos2::new(
-                        draw_pos.x - (40.0 / area.height() * self.internal_area.height()),
-                        draw_pos.y - (40.0 / area.height() * self.internal_area.height()),
-                    );
-                    let right_bottom = Pos2::new(
-                        draw_pos.x + (40.0 / area.height() * self.internal_area.height()),
-                        draw_pos.y + (40.0 / area.height() * self.internal_area.height()),
-                    );
-                    let rect = Rect {
-                        min: left_top,
-                        max: right_bottom,
-                    };
-                    painter.circle(
-                        draw_pos,
-                        40.0 / area.height() * self.internal_area.height(),
-                        Color32::WHITE,
-                        egui::Stroke::new(
-                            2.0 / area.height() * self.internal_area.height(),
-                            Color32::LIGHT_GRAY,
-                        ),
-                    );
-                    egui::Image::new(format!("{}static/icons/{}", *APP_URL, each.icon))
-                        .paint_at(ui, rect);
-                }
-            }
+                    CLUSTER_RADIUS
+                };
+                *distance < radius * scale
+            })
+            .min_by(|(_, one), (_, other)| one.total_cmp(other))
+            .map(|(marker, _)| marker)
+    }
+
+    /// How far the camera is zoomed in, 1 showing the whole map
+    fn zoom(&self) -> f32 {
+        if self.internal_area.width() <= 0.0 {
+            return 1.0;
         }
+        self.map_area.width() / self.internal_area.width()
     }
 
-    /// Handles the logic when a destination point is clicked
-    fn check_click(&mut self, ui: &egui::Ui, click_pos: Pos2, area: Rect) {
-        if let Some(data) = &self.dests.data {
-            for each in data {
-                let norm_coord = Pos2::new(
-                    (click_pos.x - area.left()) / area.width(),
-                    (click_pos.y - area.top()) / area.height(),
-                );
-                let distance = norm_coord.distance(to_norm_coords(each.longitude, each.latitude));
-                if distance < 40.0 / area.height() / area.height() * self.internal_area.height()
-                    && !self.popups.iter().any(|list| list.inner.uni_id == each.id)
-                {
-                    let initial_pos = ui
-                        .input(|input| input.pointer.interact_pos())
-                        .unwrap_or_default();
-                    let popup = ListState::new(each.title.clone(), each.id, initial_pos);
-                    self.popups.push(popup);
-                }
+    /// Groups the shown destination points that would overlap at the current zoom
+    ///
+    /// Members are indices into the dests. Once the camera cannot zoom in any further, nothing is
+    /// grouped, as such clusters could never be split up.
+    fn markers(&self, area: Rect) -> Vec<Cluster> {
+        let Some(data) = &self.dests.data else {
+            return Vec::new();
+        };
+        let mut shown: Vec<usize> = (0..data.len())
+            .filter(|&index| self.is_shown(&data[index]))
+            .collect();
+        // Dests with more alumni start the clusters, so the busy ones stay in place
+        shown.sort_by_key(|&index| std::cmp::Reverse(data[index].alumni));
+        let points: Vec<Pos2> = shown
+            .iter()
+            .map(|&index| {
+                let each = &data[index].university;
+                to_ui_coords(self.projection.to_norm(each.longitude, each.latitude), area)
+            })
+            .collect();
+        let distance = if self.zoom() >= camera::MAX_ZOOM * 0.99 {
+            0.0
+        } else {
+            CLUSTER_DISTANCE * self.internal_area.height() / area.height()
+        };
+        let mut markers = cluster::cluster(&points, distance);
+        for marker in &mut markers {
+            for member in &mut marker.members {
+                *member = shown[*member];
             }
         }
+        markers
     }
 
-    /// Handles the logic when the cursor hovers over a destination point
-    fn check_hover(&mut self, ui: &egui::Ui, hover_pos: Pos2, area: Rect) {
-        if let Some(data) = &self.base.data {
+    /// Handles the logic when the cursor hovers over a base point or a marker
+    fn check_hover(&mut self, ui: &egui::Ui, hover_pos: Pos2, area: Rect, markers: &[Cluster]) {
+        for origin in self.shown_bases() {
             let norm_coord = Pos2::new(
                 (hover_pos.x - area.left()) / area.width(),
                 (hover_pos.y - area.top()) / area.height(),
             );
-            let distance = norm_coord.distance(to_norm_coords(data.longitude, data.latitude));
+            let distance = norm_coord.distance(
+                self.projection
+                    .to_norm(origin.base.longitude, origin.base.latitude),
+            );
             if distance < 20.0 / area.height() / area.height() * self.internal_area.height() {
                 egui::show_tooltip_at_pointer(
                     ui.ctx(),
                     ui.layer_id(),
                     egui::Id::new("dest_points_tooltip"),
                     |ui| {
-                        ui.add(egui::Label::new(&data.title).extend());
+                        ui.add(egui::Label::new(&origin.base.title).extend());
                     },
                 );
             }
         }
-        if let Some(data) = &self.dests.data {
-            for each in data {
-                let norm_coord = Pos2::new(
-                    (hover_pos.x - area.left()) / area.width(),
-                    (hover_pos.y - area.top()) / area.height(),
-                );
-                let distance = norm_coord.distance(to_norm_coords(each.longitude, each.latitude));
-                if distance < 40.0 / area.height() / area.height() * self.internal_area.height() {
-                    egui::show_tooltip_at_pointer(
-                        ui.ctx(),
-                        ui.layer_id(),
-                        egui::Id::new("dest_points_tooltip"),
-                        |ui| {
-                            ui.label(&each.title);
-                        },
-                    );
-                    Self::remove_from_list(&mut self.highlights, each);
+        let Some(marker) = self.marker_at(hover_pos, area, markers) else {
+            return;
+        };
+        let Some(data) = &self.dests.data else {
+            return;
+        };
+        egui::show_tooltip_at_pointer(
+            ui.ctx(),
+            ui.layer_id(),
+            egui::Id::new("dest_points_tooltip"),
+            |ui| {
+                if let [index] = marker.members[..] {
+                    ui.label(&data[index].university.title);
+                    ui.label(alumni_text(data[index].alumni));
+                    return;
                 }
-            }
-        }
-    }
-    fn remove_from_list(list: &mut Vec<university::Model>, item: &university::Model) {
-        let mut removing = Vec::new();
-        for (index, each) in list.iter().enumerate() {
-            if each == item {
-                removing.push(index);
-            }
-        }
-        for each in removing {
-            list.remove(each);
+                for &index in &marker.members {
+                    let dest = &data[index];
+                    ui.label(format!(
+                        "{}: {}",
+                        dest.university.title,
+                        alumni_text(dest.alumni)
+                    ));
+                }
+                ui.label("Click to zoom in");
+            },
+        );
+        if let [index] = marker.members[..] {
+            let uni_id = data[index].university.id;
+            self.highlights.retain(|id| *id != uni_id);
         }
     }
 }
 
-/// Translates longitude by the offset value
-/// Will wrap from 180 to -180 if exceeds the boundary
-fn offset_longitude(longitude: f32) -> f32 {
-    let result = longitude + MAP_LONGITUDE_OFFSET;
-    if result > 180.0 {
-        result - 360.0
-    } else if result < -180.0 {
-        result + 360.0
-    } else {
-        result
-    }
+/// Fetches the base points, with their dests of the given class only if any
+fn fetch_bases(class_of: Option<i32>) -> FetchedData<Vec<base::WithDestinations>> {
+    let url = fetcher::with_query(
+        format!("{}api/v2/bases", *APP_URL),
+        &[("class_of", class_of)],
+    );
+    FetchedData::new(url, |response| {
+        response.json::<Vec<base::WithDestinations>>().ok()
+    })
 }
-/// Translates latitude by the offset value
-/// Will wrap from 90 to -90 if exceeds the boundary
-fn offset_latitude(latitude: f32) -> f32 {
-    let result = latitude + MAP_LATITUDE_OFFSET;
-    if result > 90.0 {
-        result - 180.0
-    } else if result < -90.0 {
-        result + 180.0
-    } else {
-        result
-    }
+
+/// Fetches the dests with their number of alumni, only those of the given class if any
+fn fetch_dests(class_of: Option<i32>) -> FetchedData<Vec<university::WithAlumni>> {
+    let url = fetcher::with_query(
+        format!("{}api/v2/alumni_counts", *APP_URL),
+        &[("class_of", class_of)],
+    );
+    FetchedData::new(url, |response| {
+        response.json::<Vec<university::WithAlumni>>().ok()
+    })
 }
 
-/// Converts longitude and latitude to normalised coordinates
-///
-/// The offset value will be added to the provided value in order to translate the map if it's not
-/// centered at the 0 degree longitude.
-fn to_norm_coords(longitude: f32, latitude: f32) -> Pos2 {
-    Pos2::new(
-        (offset_longitude(longitude) * LONGITUDE_SCALE + FINAL_LONGITUDE_OFFSET) / 360.0 + 0.5,
-        0.5 - (offset_latitude(latitude) * LATITUDE_SCALE + FINAL_LATITUDE_OFFSET) / 180.0,
-    )
+/// Width of the line to a dest, growing slower the more alumni went there
+fn line_width(alumni: i64) -> f32 {
+    let alumni = f32::from(u16::try_from(alumni).unwrap_or(u16::MAX));
+    (2.0 + 3.0 * alumni.sqrt()).min(20.0)
+}
+
+fn alumni_text(alumni: i64) -> String {
+    if alumni == 1 {
+        "1 alumnus".to_string()
+    } else {
+        format!("{alumni} alumni")
+    }
 }
 
 /// Converts normalised coordinates to ui coordinates
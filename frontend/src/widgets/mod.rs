@@ -1,4 +1,9 @@
+pub mod camera;
 pub mod card;
+pub mod cluster;
+pub mod geodesic;
 pub mod list;
 pub mod map;
+pub mod popups;
+pub mod projection;
 pub mod search;
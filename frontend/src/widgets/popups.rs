@@ -0,0 +1,198 @@
+//! Keeps track of the open lists, floating over the map or docked to its side
+
+use egui::{Pos2, Vec2};
+
+use crate::widgets::list::ListState;
+
+/// Most lists open at once, opening another closes the oldest
+const MAX_OPEN: usize = 6;
+/// How far each floating list is moved from the previous one, so they do not cover each other
+const CASCADE_STEP: f32 = 24.0;
+/// Lists cascade this far before starting again at the click
+const CASCADE_DEPTH: usize = 5;
+const DOCK_WIDTH: f32 = 320.0;
+
+/// Every open [`ListState`], oldest first
+pub struct Popups {
+    lists: Vec<ListState>,
+    /// Id of the next list, so windows keep their place while others come and go
+    next_id: u64,
+    /// Whether lists are shown in a panel beside the map instead of floating over it
+    pub docked: bool,
+}
+
+/// Data manipulation
+impl Popups {
+    pub fn new() -> Self {
+        Self {
+            lists: Vec::new(),
+            next_id: 0,
+            docked: false,
+        }
+    }
+
+    /// Opens a list, placing it a little off the other lists opened around the same point
+    pub fn open(&mut self, mut list: ListState) {
+        if self.lists.len() >= MAX_OPEN {
+            self.lists.remove(0);
+        }
+        list.cascade(self.cascade_offset(list.anchor));
+        list.id = self.next_id;
+        self.next_id += 1;
+        self.lists.push(list);
+    }
+
+    /// How far to move a list asked for at `anchor`, one step for each list already around it
+    #[allow(clippy::cast_precision_loss)]
+    fn cascade_offset(&self, anchor: Pos2) -> Vec2 {
+        let reach = CASCADE_STEP * CASCADE_DEPTH as f32;
+        let stacked = self
+            .lists
+            .iter()
+            .filter(|each| each.anchor.distance(anchor) < reach)
+            .count()
+            % CASCADE_DEPTH;
+        Vec2::splat(CASCADE_STEP * stacked as f32)
+    }
+
+    /// Closes every list
+    pub fn clear(&mut self) {
+        self.lists.clear();
+    }
+
+    /// Keeps only the lists the predicate holds for
+    pub fn retain(&mut self, keep: impl FnMut(&ListState) -> bool) {
+        self.lists.retain(keep);
+    }
+
+    /// Whether the list of the given university is open
+    pub fn contains(&self, uni_id: i32) -> bool {
+        self.lists.iter().any(|each| each.inner.uni_id == uni_id)
+    }
+
+    /// The list opened last
+    pub fn latest(&self) -> Option<&ListState> {
+        self.lists.last()
+    }
+}
+
+/// Graphics
+impl Popups {
+    /// Draws the lists as windows unless they are docked, returning the university whose list
+    /// asked to be shown on the map
+    pub fn render_floating(&mut self, ctx: &egui::Context) -> Option<i32> {
+        if !self.docked {
+            for each in &mut self.lists {
+                each.render(ctx);
+            }
+        }
+        self.finish()
+    }
+
+    /// Draws the panel of docked lists, if they are docked and there are any, returning the
+    /// university whose list asked to be shown on the map
+    ///
+    /// Has to be called before the central panel, so the map makes room for it.
+    pub fn render_docked(&mut self, ctx: &egui::Context) -> Option<i32> {
+        if !self.docked || self.lists.is_empty() {
+            return None;
+        }
+        egui::SidePanel::right("docked_lists")
+            .default_width(DOCK_WIDTH)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    // Newest on top, where it is seen first
+                    for each in self.lists.iter_mut().rev() {
+                        each.render_docked(ui);
+                    }
+                });
+            });
+        self.finish()
+    }
+
+    /// Lets the user dock the lists and close them all
+    pub fn render_controls(&mut self, ui: &mut egui::Ui) {
+        ui.toggle_value(&mut self.docked, "Dock lists")
+            .on_hover_text("Show lists beside the map instead of over it");
+        if ui
+            .add_enabled(!self.lists.is_empty(), egui::Button::new("Close all"))
+            .clicked()
+        {
+            self.lists.clear();
+        }
+    }
+
+    /// Drops the lists closed this frame, returning the university a list asked to be shown
+    fn finish(&mut self) -> Option<i32> {
+        self.lists.retain(|each| each.open);
+        self.lists
+            .iter_mut()
+            .filter_map(|each| std::mem::take(&mut each.locate).then_some(each.inner.uni_id))
+            .last()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A list of the given university, asked for at the given point
+    fn list(uni_id: i32, anchor: Pos2) -> ListState {
+        ListState::new(format!("University {uni_id}"), uni_id, None, None, anchor)
+    }
+
+    fn ids(popups: &Popups) -> Vec<u64> {
+        popups.lists.iter().map(|each| each.id).collect()
+    }
+
+    #[test]
+    fn opening_too_many_closes_the_oldest() {
+        let mut popups = Popups::new();
+        for uni_id in 0..=6 {
+            #[allow(clippy::cast_precision_loss)]
+            popups.open(list(uni_id, Pos2::new(uni_id as f32 * 500.0, 0.0)));
+        }
+        assert_eq!(popups.lists.len(), MAX_OPEN);
+        assert!(!popups.contains(0));
+        assert!((1..=6).all(|uni_id| popups.contains(uni_id)));
+        assert_eq!(ids(&popups), [1, 2, 3, 4, 5, 6]);
+        assert_eq!(popups.latest().map(|each| each.inner.uni_id), Some(6));
+    }
+
+    #[test]
+    fn ids_survive_closing() {
+        let mut popups = Popups::new();
+        for uni_id in 0..4 {
+            popups.open(list(uni_id, Pos2::ZERO));
+        }
+        // Two windows closed in the same frame, one of them asking to be located first
+        popups.lists[1].open = false;
+        popups.lists[2].open = false;
+        popups.lists[2].locate = true;
+        popups.lists[3].locate = true;
+        assert_eq!(popups.finish(), Some(3));
+        assert_eq!(ids(&popups), [0, 3]);
+        assert!(!popups.lists[1].locate);
+
+        popups.open(list(1, Pos2::ZERO));
+        assert_eq!(ids(&popups), [0, 3, 4]);
+        popups.clear();
+        popups.open(list(1, Pos2::ZERO));
+        assert_eq!(ids(&popups), [5]);
+    }
+
+    #[test]
+    fn cascade_wraps() {
+        let mut popups = Popups::new();
+        let anchor = Pos2::new(100.0, 100.0);
+        let mut offsets = Vec::new();
+        for uni_id in 0..MAX_OPEN {
+            offsets.push(popups.cascade_offset(anchor).x / CASCADE_STEP);
+            popups.open(list(i32::try_from(uni_id).unwrap(), anchor));
+        }
+        assert_eq!(offsets, [0.0, 1.0, 2.0, 3.0, 4.0, 0.0]);
+
+        // Lists elsewhere do not push it away
+        assert_eq!(popups.cascade_offset(Pos2::new(1000.0, 100.0)), Vec2::ZERO);
+    }
+}
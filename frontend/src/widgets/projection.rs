@@ -0,0 +1,309 @@
+//! Lines up longitude and latitude with the picture under the map
+//!
+//! Projections work on offset coordinates in radians, giving points between -0.5 and 0.5 on
+//! both axes with north up. [`Calibrated`] turns those into normalised coordinates of the picture,
+//! where (0, 0) is its upper left and (1, 1) its lower right.
+
+use egui::Pos2;
+use entity::map::{self, MapConfig};
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI, TAU};
+
+/// A way of flattening the globe
+pub trait Projection {
+    /// Projects a point onto the plane
+    fn project(&self, longitude: f32, latitude: f32) -> Pos2;
+
+    /// Finds the point that projects to the given one
+    fn unproject(&self, point: Pos2) -> (f32, f32);
+}
+
+/// Longitude and latitude spaced evenly
+pub struct Equirectangular;
+
+impl Projection for Equirectangular {
+    fn project(&self, longitude: f32, latitude: f32) -> Pos2 {
+        Pos2::new(longitude / TAU, latitude / PI)
+    }
+
+    fn unproject(&self, point: Pos2) -> (f32, f32) {
+        (point.x * TAU, point.y * PI)
+    }
+}
+
+/// Mercator on a square, as used by most web maps
+pub struct WebMercator;
+
+impl WebMercator {
+    /// Latitude where the square ends
+    const MAX_LATITUDE: f32 = 1.484_422_2;
+}
+
+impl Projection for WebMercator {
+    fn project(&self, longitude: f32, latitude: f32) -> Pos2 {
+        let latitude = latitude.clamp(-Self::MAX_LATITUDE, Self::MAX_LATITUDE);
+        Pos2::new(
+            longitude / TAU,
+            (FRAC_PI_4 + latitude / 2.0).tan().ln() / TAU,
+        )
+    }
+
+    fn unproject(&self, point: Pos2) -> (f32, f32) {
+        (
+            point.x * TAU,
+            2.0 * (point.y * TAU).exp().atan() - FRAC_PI_2,
+        )
+    }
+}
+
+/// Robinson's compromise, interpolated from his table
+pub struct Robinson;
+
+impl Robinson {
+    /// Length of the parallel and distance from the equator, every 5 degrees from the equator
+    const TABLE: [(f32, f32); 19] = [
+        (1.0000, 0.0000),
+        (0.9986, 0.0620),
+        (0.9954, 0.1240),
+        (0.9900, 0.1860),
+        (0.9822, 0.2480),
+        (0.9730, 0.3100),
+        (0.9600, 0.3720),
+        (0.9427, 0.4340),
+        (0.9216, 0.4958),
+        (0.8962, 0.5571),
+        (0.8679, 0.6176),
+        (0.8350, 0.6769),
+        (0.7986, 0.7346),
+        (0.7597, 0.7903),
+        (0.7186, 0.8435),
+        (0.6732, 0.8936),
+        (0.6213, 0.9394),
+        (0.5722, 0.9761),
+        (0.5322, 1.0000),
+    ];
+    const STEP: f32 = PI / 36.0;
+
+    /// Interpolates the table at the given latitude, ignoring its sign
+    fn lookup(latitude: f32) -> (f32, f32) {
+        let position = (latitude.abs() / Self::STEP).min(18.0);
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let index = (position.floor() as usize).min(17);
+        #[allow(clippy::cast_precision_loss)]
+        let fraction = position - index as f32;
+        let (length, distance) = Self::TABLE[index];
+        let (next_length, next_distance) = Self::TABLE[index + 1];
+        (
+            length + (next_length - length) * fraction,
+            distance + (next_distance - distance) * fraction,
+        )
+    }
+}
+
+impl Projection for Robinson {
+    fn project(&self, longitude: f32, latitude: f32) -> Pos2 {
+        let (length, distance) = Self::lookup(latitude);
+        Pos2::new(length * longitude / TAU, distance.copysign(latitude) / 2.0)
+    }
+
+    fn unproject(&self, point: Pos2) -> (f32, f32) {
+        let distance = (point.y.abs() * 2.0).min(1.0);
+        let index = Self::TABLE
+            .windows(2)
+            .position(|pair| distance <= pair[1].1)
+            .unwrap_or(17);
+        let (_, low) = Self::TABLE[index];
+        let (_, high) = Self::TABLE[index + 1];
+        #[allow(clippy::cast_precision_loss)]
+        let latitude = (index as f32 + (distance - low) / (high - low)) * Self::STEP;
+        let (length, _) = Self::lookup(latitude);
+        (point.x * TAU / length, latitude.copysign(point.y))
+    }
+}
+
+/// A projection lined up with a particular picture
+pub struct Calibrated {
+    projection: Box<dyn Projection>,
+    config: MapConfig,
+}
+
+impl Calibrated {
+    pub fn new(config: MapConfig) -> Self {
+        let projection: Box<dyn Projection> = match config.projection {
+            map::Projection::Equirectangular => Box::new(Equirectangular),
+            map::Projection::WebMercator => Box::new(WebMercator),
+            map::Projection::Robinson => Box::new(Robinson),
+        };
+        Self { projection, config }
+    }
+
+    /// The config this was made from
+    pub fn config(&self) -> &MapConfig {
+        &self.config
+    }
+
+    /// Converts longitude and latitude to normalised coordinates of the picture
+    pub fn to_norm(&self, longitude: f32, latitude: f32) -> Pos2 {
+        self.shifted_to_norm(
+            wrap(longitude + self.config.longitude_offset, 180.0),
+            latitude,
+        )
+    }
+
+    /// Converts a path to normalised coordinates, cutting it in pieces where it crosses the edge
+    /// of the picture
+    pub fn to_norm_path(&self, path: &[(f32, f32)]) -> Vec<Vec<Pos2>> {
+        let mut pieces = Vec::new();
+        let mut piece = Vec::new();
+        let mut last: Option<(f32, f32)> = None;
+        for &(longitude, latitude) in path {
+            let shifted = wrap(longitude + self.config.longitude_offset, 180.0);
+            if let Some((last_shifted, last_latitude)) = last {
+                if (shifted - last_shifted).abs() > 180.0 {
+                    // Meets the edge on the side it comes from and goes on from the other side
+                    let edge = 180.0_f32.copysign(last_shifted);
+                    let unwrapped = shifted + 2.0 * edge;
+                    let t = (edge - last_shifted) / (unwrapped - last_shifted);
+                    let crossing = last_latitude + (latitude - last_latitude) * t;
+                    piece.push(self.shifted_to_norm(edge, crossing));
+                    pieces.push(std::mem::take(&mut piece));
+                    piece.push(self.shifted_to_norm(-edge, crossing));
+                }
+            }
+            piece.push(self.shifted_to_norm(shifted, latitude));
+            last = Some((shifted, latitude));
+        }
+        pieces.push(piece);
+        pieces
+    }
+
+    /// Converts a longitude already offset and wrapped
+    fn shifted_to_norm(&self, shifted_longitude: f32, latitude: f32) -> Pos2 {
+        let config = &self.config;
+        let point = self.projection.project(
+            shifted_longitude.to_radians(),
+            wrap(latitude + config.latitude_offset, 90.0).to_radians(),
+        );
+        Pos2::new(
+            point.x * config.longitude_scale + config.final_longitude_offset / 360.0 + 0.5,
+            0.5 - (point.y * config.latitude_scale + config.final_latitude_offset / 180.0),
+        )
+    }
+
+    /// Converts normalised coordinates of the picture back to longitude and latitude
+    pub fn to_lon_lat(&self, norm: Pos2) -> (f32, f32) {
+        let config = &self.config;
+        let point = Pos2::new(
+            (norm.x - 0.5 - config.final_longitude_offset / 360.0) / config.longitude_scale,
+            (0.5 - norm.y - config.final_latitude_offset / 180.0) / config.latitude_scale,
+        );
+        let (longitude, latitude) = self.projection.unproject(point);
+        (
+            wrap(longitude.to_degrees() - config.longitude_offset, 180.0),
+            wrap(latitude.to_degrees() - config.latitude_offset, 90.0),
+        )
+    }
+}
+
+impl Default for Calibrated {
+    fn default() -> Self {
+        Self::new(MapConfig::default())
+    }
+}
+
+/// Wraps a value into `-limit..=limit`, going around once at most
+fn wrap(value: f32, limit: f32) -> f32 {
+    if value > limit {
+        value - 2.0 * limit
+    } else if value < -limit {
+        value + 2.0 * limit
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    #[test]
+    fn unproject_reverses_project() {
+        let projections: [(&str, &dyn Projection); 3] = [
+            ("equirectangular", &Equirectangular),
+            ("web mercator", &WebMercator),
+            ("robinson", &Robinson),
+        ];
+        for (name, projection) in projections {
+            for longitude in (-180..=180).step_by(20) {
+                for latitude in (-85..=85).step_by(5) {
+                    #[allow(clippy::cast_precision_loss)]
+                    let (longitude, latitude) = (
+                        (longitude as f32).to_radians(),
+                        (latitude as f32).to_radians(),
+                    );
+                    let point = projection.project(longitude, latitude);
+                    let (back_lon, back_lat) = projection.unproject(point);
+                    assert!(
+                        close(back_lon, longitude) && close(back_lat, latitude),
+                        "{name}: ({longitude}, {latitude}) came back as ({back_lon}, {back_lat})"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn web_mercator_is_square() {
+        let top = WebMercator.project(PI, WebMercator::MAX_LATITUDE);
+        let bottom = WebMercator.project(PI, -WebMercator::MAX_LATITUDE);
+        assert!(close(top.x, 0.5) && close(top.y, 0.5), "{top:?}");
+        assert!(close(bottom.y, -0.5), "{bottom:?}");
+        // Poles are infinitely far away, they stay on the edge instead
+        for latitude in [86.0_f32, 89.9, 90.0] {
+            let point = WebMercator.project(0.0, latitude.to_radians());
+            assert_eq!(point.y, top.y);
+            let point = WebMercator.project(0.0, -latitude.to_radians());
+            assert_eq!(point.y, bottom.y);
+        }
+    }
+
+    #[test]
+    fn calibrated_offsets_and_scales() {
+        let calibrated = Calibrated::new(MapConfig {
+            projection: map::Projection::Equirectangular,
+            longitude_offset: 30.0,
+            latitude_offset: 0.0,
+            longitude_scale: 0.5,
+            latitude_scale: 0.8,
+            final_longitude_offset: 36.0,
+            final_latitude_offset: 18.0,
+            ..MapConfig::default()
+        });
+        // Moved 30 degrees east, halved, then a tenth of the picture right and up
+        let cases = [
+            ((0.0, 0.0), Pos2::new(30.0 / 720.0 + 0.6, 0.4)),
+            ((0.0, 45.0), Pos2::new(30.0 / 720.0 + 0.6, 0.2)),
+            // Going past 180 degrees comes back in from the west
+            ((170.0, -45.0), Pos2::new(-160.0 / 720.0 + 0.6, 0.6)),
+        ];
+        for ((longitude, latitude), expected) in cases {
+            let norm = calibrated.to_norm(longitude, latitude);
+            assert!(
+                close(norm.x, expected.x) && close(norm.y, expected.y),
+                "({longitude}, {latitude}) went to {norm:?} instead of {expected:?}"
+            );
+            let (back_lon, back_lat) = calibrated.to_lon_lat(norm);
+            assert!(close(back_lon, longitude) && close(back_lat, latitude));
+        }
+
+        let default = Calibrated::default();
+        for (longitude, latitude) in [(-71.09, 42.36), (116.33, 40.0), (151.2, -33.87)] {
+            let (back_lon, back_lat) = default.to_lon_lat(default.to_norm(longitude, latitude));
+            assert!(close(back_lon, longitude) && close(back_lat, latitude));
+        }
+    }
+}
@@ -1,12 +1,31 @@
+//! The search bar, suggesting universities and classmates as you type
+
 use crate::app::APP_URL;
-use crate::fetcher::FetchedData;
+use crate::fetcher::{self, FetchedData};
+use crate::widgets::camera;
 use crate::widgets::map::WorldMap;
-use egui::FontId;
-use entity::university;
+use egui::{FontId, Key, Modifiers, Pos2};
+use entity::search::{Field, Hit};
+use std::time::Duration;
+
+/// How long typing has to pause before suggestions are fetched, in seconds
+const DEBOUNCE: f64 = 0.3;
+/// At most this many suggestions are listed
+const MAX_SUGGESTIONS: usize = 10;
+/// Characters of a matching major or bio shown in a suggestion
+const MAX_EXCERPT: usize = 32;
 
 pub struct Search {
-    fetcher: Option<FetchedData<Vec<university::Model>>>,
+    fetcher: Option<FetchedData<Vec<Hit>>>,
     text: String,
+    /// When the text was last edited, if it has not been searched for yet
+    edited_at: Option<f64>,
+    suggestions: Vec<Hit>,
+    /// Index of the suggestion chosen with the arrow keys
+    selected: Option<usize>,
+    dropdown_open: bool,
+    /// Whether to show every hit once they arrive, instead of suggesting them
+    show_all: bool,
 }
 
 /// Data manipulation
@@ -15,6 +34,100 @@ impl Search {
         Self {
             fetcher: None,
             text: String::new(),
+            edited_at: None,
+            suggestions: Vec::new(),
+            selected: None,
+            dropdown_open: false,
+            show_all: false,
+        }
+    }
+
+    /// Fetches the hits for the current text
+    fn fetch(&mut self) {
+        self.edited_at = None;
+        self.fetcher = Some(FetchedData::new(
+            format!(
+                "{}api/v2/search?q={}",
+                *APP_URL,
+                fetcher::encode_component(self.text.trim())
+            ),
+            |response| response.json::<Vec<Hit>>().ok(),
+        ));
+    }
+
+    /// Forgets the text and everything found with it
+    fn reset(&mut self) {
+        self.text = String::new();
+        self.edited_at = None;
+        self.fetcher = None;
+        self.suggestions.clear();
+        self.selected = None;
+        self.dropdown_open = false;
+        self.show_all = false;
+    }
+
+    /// Moves the selection up or down the suggestions, wrapping around
+    fn move_selection(&mut self, down: bool) {
+        let count = self.suggestions.len().min(MAX_SUGGESTIONS);
+        if count == 0 {
+            return;
+        }
+        self.selected = Some(match (self.selected, down) {
+            (None, true) => 0,
+            (None, false) => count - 1,
+            (Some(index), true) => (index + 1) % count,
+            (Some(index), false) => (index + count - 1) % count,
+        });
+    }
+
+    /// Highlights every university found, and opens the list of each profile's university
+    /// scrolled to the first profile found there
+    fn show_hits(hits: &[Hit], world_map: &mut WorldMap, pos: Pos2) {
+        let mut opened = Vec::new();
+        for hit in hits {
+            match hit {
+                Hit::University(university) => world_map.highlights.push(university.id),
+                Hit::Profile {
+                    profile,
+                    university,
+                    ..
+                } => {
+                    world_map.highlights.push(profile.university_id);
+                    if !opened.contains(&profile.university_id) {
+                        opened.push(profile.university_id);
+                        world_map.open_profile(
+                            university.clone(),
+                            profile.university_id,
+                            profile.id,
+                            pos,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Moves the map to a single hit and opens its list
+    fn pick(hit: &Hit, world_map: &mut WorldMap, pos: Pos2) {
+        match hit {
+            Hit::University(university) => {
+                world_map.fly_to(
+                    university.longitude,
+                    university.latitude,
+                    camera::FOCUS_ZOOM,
+                );
+                world_map.highlights.push(university.id);
+                world_map.open_university(university, pos);
+            }
+            Hit::Profile {
+                profile,
+                university,
+                ..
+            } => {
+                world_map.fly_to_university(profile.university_id);
+                world_map.highlights.push(profile.university_id);
+                world_map.open_profile(university.clone(), profile.university_id, profile.id, pos);
+            }
         }
     }
 }
@@ -22,33 +135,199 @@ impl Search {
 /// Graphics
 impl Search {
     pub fn render(&mut self, ui: &mut egui::Ui, world_map: &mut WorldMap) {
-        ui.horizontal(|ui| {
-            let input_response = ui.add(
-                egui::TextEdit::singleline(&mut self.text)
-                    .hint_text("Search...")
-                    .font(FontId::proportional(24.0)),
-            );
-            let button_response = ui.button(" ");
-            if input_response.lost_focus() && (ui.input(|i| i.key_pressed(egui::Key::Enter)))
-                || button_response.clicked()
-            {
-                self.fetcher = Some(FetchedData::new(
-                    format!("{}api/search/universities/{}", *APP_URL, self.text),
-                    |response| {
-                        let str: String = response.json().unwrap_or_default();
-                        serde_json::from_str::<Vec<university::Model>>(&str).ok()
-                    },
-                ));
-                self.text = String::new();
-            }
-
-            if let Some(fetching) = &mut self.fetcher {
-                fetching.poll(ui.ctx());
-                if let Some(data) = &mut fetching.data {
-                    world_map.highlights.append(data);
-                    self.fetcher = None;
+        let text_id = egui::Id::new("search_text");
+        let popup_pos = ui.ctx().screen_rect().center();
+
+        // Arrow keys pick a suggestion instead of moving the cursor
+        if self.dropdown_open && ui.memory(|memory| memory.has_focus(text_id)) {
+            if ui.input_mut(|input| input.consume_key(Modifiers::NONE, Key::ArrowDown)) {
+                self.move_selection(true);
+            }
+            if ui.input_mut(|input| input.consume_key(Modifiers::NONE, Key::ArrowUp)) {
+                self.move_selection(false);
+            }
+            if ui.input_mut(|input| input.consume_key(Modifiers::NONE, Key::Escape)) {
+                self.dropdown_open = false;
+            }
+        }
+
+        let input_response = ui
+            .horizontal(|ui| {
+                let input_response = ui.add(
+                    egui::TextEdit::singleline(&mut self.text)
+                        .id(text_id)
+                        .hint_text("Search...")
+                        .font(FontId::proportional(24.0)),
+                );
+                let button_response = ui.button(" ");
+                if input_response.changed() {
+                    self.selected = None;
+                    // Results of an earlier Enter are for a different text by now
+                    self.show_all = false;
+                    if self.text.trim().is_empty() {
+                        self.reset();
+                    } else {
+                        self.edited_at = Some(ui.input(|input| input.time));
+                    }
+                }
+                let entered =
+                    input_response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+                if (entered || button_response.clicked()) && !self.text.trim().is_empty() {
+                    if let Some(hit) = self.selected.and_then(|index| self.suggestions.get(index)) {
+                        Self::pick(hit, world_map, popup_pos);
+                        self.reset();
+                    } else {
+                        self.show_all = true;
+                        self.fetch();
+                    }
                 }
+                input_response
+            })
+            .inner;
+
+        // Wait for typing to pause before asking for suggestions
+        if let Some(edited_at) = self.edited_at {
+            let waited = ui.input(|input| input.time) - edited_at;
+            if waited >= DEBOUNCE {
+                self.fetch();
+            } else {
+                ui.ctx()
+                    .request_repaint_after(Duration::from_secs_f64(DEBOUNCE - waited));
             }
-        });
+        }
+
+        if let Some(fetching) = &mut self.fetcher {
+            fetching.poll(ui.ctx());
+            if fetching.done {
+                let hits = fetching.data.take().unwrap_or_default();
+                self.fetcher = None;
+                if self.show_all {
+                    Self::show_hits(&hits, world_map, popup_pos);
+                    self.reset();
+                } else {
+                    self.dropdown_open = !hits.is_empty();
+                    self.suggestions = hits;
+                    self.selected = None;
+                }
+            }
+        }
+
+        if self.dropdown_open {
+            self.render_dropdown(ui, &input_response, world_map, popup_pos);
+        }
+    }
+
+    /// Lists the suggestions below the search bar
+    fn render_dropdown(
+        &mut self,
+        ui: &egui::Ui,
+        input_response: &egui::Response,
+        world_map: &mut WorldMap,
+        popup_pos: Pos2,
+    ) {
+        let mut picked = None;
+        let area = egui::Area::new(egui::Id::new("search_suggestions"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(input_response.rect.left_bottom())
+            .show(ui.ctx(), |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_width(input_response.rect.width());
+                    for (index, hit) in self.suggestions.iter().take(MAX_SUGGESTIONS).enumerate() {
+                        let selected = self.selected == Some(index);
+                        let response = ui.selectable_label(selected, suggestion_text(hit));
+                        if selected {
+                            response.scroll_to_me(None);
+                        }
+                        if response.clicked() {
+                            picked = Some(index);
+                        }
+                    }
+                });
+            });
+
+        if let Some(index) = picked {
+            Self::pick(&self.suggestions[index], world_map, popup_pos);
+            self.reset();
+        } else if input_response.clicked_elsewhere() && !area.response.contains_pointer() {
+            self.dropdown_open = false;
+        }
+    }
+}
+
+/// What a suggestion says about its hit, with the major or bio if that is what matched
+fn suggestion_text(hit: &Hit) -> String {
+    match hit {
+        Hit::University(university) => format!("󰑴 {}", university.title),
+        Hit::Profile {
+            profile,
+            university,
+            matched,
+        } => {
+            let name = match &profile.name_supplementary {
+                Some(supplementary) => format!("{} {supplementary}", profile.name_primary),
+                None => profile.name_primary.clone(),
+            };
+            let mut text = format!("󰀄 {name} · {university}");
+            if matches!(matched, Field::Major | Field::Bio) {
+                let field = matched.of(profile);
+                text.push_str(" · ");
+                text.extend(field.chars().take(MAX_EXCERPT));
+                if field.chars().count() > MAX_EXCERPT {
+                    text.push('…');
+                }
+            }
+            text
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use entity::profile;
+
+    fn hit(matched: Field, bio: &str) -> Hit {
+        Hit::Profile {
+            profile: profile::Model {
+                id: 1,
+                name_primary: "李华".to_string(),
+                name_supplementary: Some("Li Hua".to_string()),
+                avatar: "lihua.png".to_string(),
+                class_of: 2025,
+                university_id: 2,
+                base_id: None,
+                major: Some("Physics".to_string()),
+                bio: Some(bio.to_string()),
+                email: None,
+                qq: None,
+                wechat: None,
+                matrix: None,
+                contact_visibility: profile::Visibility::Public,
+                contacts_hidden: false,
+            },
+            university: "Tsinghua".to_string(),
+            matched,
+        }
+    }
+
+    #[test]
+    fn suggestions_show_what_matched() {
+        let name = "󰀄 李华 Li Hua · Tsinghua";
+        for matched in [Field::NamePrimary, Field::NameSupplementary] {
+            assert_eq!(suggestion_text(&hit(matched, "Rows boats")), name);
+        }
+        assert_eq!(
+            suggestion_text(&hit(Field::Major, "Rows boats")),
+            format!("{name} · Physics")
+        );
+        assert_eq!(
+            suggestion_text(&hit(Field::Bio, "Rows boats")),
+            format!("{name} · Rows boats")
+        );
+        let long = "划船".repeat(20);
+        assert_eq!(
+            suggestion_text(&hit(Field::Bio, &long)),
+            format!("{name} · {}…", "划船".repeat(16))
+        );
     }
 }
@@ -2,6 +2,12 @@ pub use sea_orm_migration::prelude::*;
 
 mod m20250522_121745_create_table_universities;
 mod m20250522_122704_create_table_profiles;
+mod m20250601_103012_create_table_accounts;
+mod m20250601_103547_create_table_sessions;
+mod m20250608_141122_add_contact_visibility_to_profiles;
+mod m20250615_093015_add_university_foreign_key_to_profiles;
+mod m20250622_104512_create_table_bases;
+mod m20250622_110240_add_base_to_profiles;
 
 pub struct Migrator;
 
@@ -11,6 +17,12 @@ impl MigratorTrait for Migrator {
         vec![
             Box::new(m20250522_121745_create_table_universities::Migration),
             Box::new(m20250522_122704_create_table_profiles::Migration),
+            Box::new(m20250601_103012_create_table_accounts::Migration),
+            Box::new(m20250601_103547_create_table_sessions::Migration),
+            Box::new(m20250608_141122_add_contact_visibility_to_profiles::Migration),
+            Box::new(m20250615_093015_add_university_foreign_key_to_profiles::Migration),
+            Box::new(m20250622_104512_create_table_bases::Migration),
+            Box::new(m20250622_110240_add_base_to_profiles::Migration),
         ]
     }
 }
@@ -0,0 +1,159 @@
+use sea_orm_migration::sea_orm::{DbBackend, Statement};
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Clone, Copy, DeriveIden)]
+enum Profile {
+    Table,
+    Id,
+    NamePrimary,
+    NameSupplementary,
+    Avatar,
+    ClassOf,
+    UniversityId,
+    Major,
+    Bio,
+    Email,
+    QQ,
+    Wechat,
+    Matrix,
+    ContactVisibility,
+}
+
+#[derive(DeriveIden)]
+enum University {
+    Table,
+    Id,
+}
+
+const FOREIGN_KEY: &str = "fk-profile-university_id";
+
+/// Universities referred to by profiles may not be deleted, the app decides whether to refuse or
+/// to delete their profiles first.
+fn foreign_key() -> ForeignKeyCreateStatement {
+    ForeignKey::create()
+        .name(FOREIGN_KEY)
+        .from(Profile::Table, Profile::UniversityId)
+        .to(University::Table, University::Id)
+        .on_delete(ForeignKeyAction::Restrict)
+        .on_update(ForeignKeyAction::Cascade)
+        .to_owned()
+}
+
+/// The profile table as it is after every previous migration
+fn profile_table(name: impl IntoIden + 'static, with_foreign_key: bool) -> TableCreateStatement {
+    let mut table = Table::create()
+        .table(name)
+        .col(pk_auto(Profile::Id))
+        .col(string(Profile::NamePrimary))
+        .col(string_null(Profile::NameSupplementary))
+        .col(string(Profile::Avatar))
+        .col(integer(Profile::ClassOf))
+        .col(string_null(Profile::Major))
+        .col(integer(Profile::UniversityId))
+        .col(string_null(Profile::Bio))
+        .col(string_null(Profile::Email))
+        .col(string_null(Profile::QQ))
+        .col(string_null(Profile::Wechat))
+        .col(string_null(Profile::Matrix))
+        .col(string_len(Profile::ContactVisibility, 16).default("public"))
+        .to_owned();
+    if with_foreign_key {
+        table.foreign_key(&mut foreign_key());
+    }
+    table
+}
+
+/// `SQLite` cannot add or drop foreign keys, so the table is copied into a new one instead
+async fn rebuild_profile_table(
+    manager: &SchemaManager<'_>,
+    with_foreign_key: bool,
+) -> Result<(), DbErr> {
+    let rebuilt = Alias::new("profile_rebuilt");
+    let columns = [
+        Profile::Id,
+        Profile::NamePrimary,
+        Profile::NameSupplementary,
+        Profile::Avatar,
+        Profile::ClassOf,
+        Profile::UniversityId,
+        Profile::Major,
+        Profile::Bio,
+        Profile::Email,
+        Profile::QQ,
+        Profile::Wechat,
+        Profile::Matrix,
+        Profile::ContactVisibility,
+    ];
+    manager
+        .create_table(profile_table(rebuilt.clone(), with_foreign_key))
+        .await?;
+    manager
+        .exec_stmt(
+            Query::insert()
+                .into_table(rebuilt.clone())
+                .columns(columns)
+                .select_from(
+                    Query::select()
+                        .columns(columns)
+                        .from(Profile::Table)
+                        .to_owned(),
+                )
+                .map_err(|err| DbErr::Migration(err.to_string()))?
+                .to_owned(),
+        )
+        .await?;
+    manager
+        .drop_table(Table::drop().table(Profile::Table).to_owned())
+        .await?;
+    manager
+        .rename_table(Table::rename().table(rebuilt, Profile::Table).to_owned())
+        .await
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let backend = manager.get_database_backend();
+        let orphans = db
+            .query_one(Statement::from_string(
+                backend,
+                "SELECT COUNT(*) AS count FROM profile \
+                 WHERE university_id NOT IN (SELECT id FROM university)",
+            ))
+            .await?
+            .map(|row| row.try_get::<i64>("", "count"))
+            .transpose()?
+            .unwrap_or_default();
+        if orphans > 0 {
+            return Err(DbErr::Migration(format!(
+                "{orphans} profiles refer to universities that no longer exist, \
+                 find them with `alumnimap list profile --orphaned` and fix or delete them first"
+            )));
+        }
+
+        if backend == DbBackend::Sqlite {
+            rebuild_profile_table(manager, true).await
+        } else {
+            manager.create_foreign_key(foreign_key()).await
+        }
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() == DbBackend::Sqlite {
+            rebuild_profile_table(manager, false).await
+        } else {
+            manager
+                .drop_foreign_key(
+                    ForeignKey::drop()
+                        .name(FOREIGN_KEY)
+                        .table(Profile::Table)
+                        .to_owned(),
+                )
+                .await
+        }
+    }
+}
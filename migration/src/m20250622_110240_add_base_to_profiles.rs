@@ -0,0 +1,169 @@
+use sea_orm_migration::sea_orm::DbBackend;
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Clone, Copy, DeriveIden)]
+enum Profile {
+    Table,
+    Id,
+    NamePrimary,
+    NameSupplementary,
+    Avatar,
+    ClassOf,
+    UniversityId,
+    BaseId,
+    Major,
+    Bio,
+    Email,
+    QQ,
+    Wechat,
+    Matrix,
+    ContactVisibility,
+}
+
+#[derive(DeriveIden)]
+enum University {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Base {
+    Table,
+    Id,
+}
+
+const FOREIGN_KEY: &str = "fk-profile-base_id";
+
+/// Profiles of a deleted base point fall back to the default one
+fn foreign_key() -> ForeignKeyCreateStatement {
+    ForeignKey::create()
+        .name(FOREIGN_KEY)
+        .from(Profile::Table, Profile::BaseId)
+        .to(Base::Table, Base::Id)
+        .on_delete(ForeignKeyAction::SetNull)
+        .on_update(ForeignKeyAction::Cascade)
+        .to_owned()
+}
+
+/// The profile table, with or without the base point column
+fn profile_table(name: impl IntoIden + 'static, with_base: bool) -> TableCreateStatement {
+    let mut table = Table::create()
+        .table(name)
+        .col(pk_auto(Profile::Id))
+        .col(string(Profile::NamePrimary))
+        .col(string_null(Profile::NameSupplementary))
+        .col(string(Profile::Avatar))
+        .col(integer(Profile::ClassOf))
+        .col(string_null(Profile::Major))
+        .col(integer(Profile::UniversityId))
+        .col(string_null(Profile::Bio))
+        .col(string_null(Profile::Email))
+        .col(string_null(Profile::QQ))
+        .col(string_null(Profile::Wechat))
+        .col(string_null(Profile::Matrix))
+        .col(string_len(Profile::ContactVisibility, 16).default("public"))
+        .foreign_key(
+            ForeignKey::create()
+                .name("fk-profile-university_id")
+                .from(Profile::Table, Profile::UniversityId)
+                .to(University::Table, University::Id)
+                .on_delete(ForeignKeyAction::Restrict)
+                .on_update(ForeignKeyAction::Cascade),
+        )
+        .to_owned();
+    if with_base {
+        table
+            .col(integer_null(Profile::BaseId))
+            .foreign_key(&mut foreign_key());
+    }
+    table
+}
+
+/// `SQLite` cannot add foreign keys, so the table is copied into a new one instead
+///
+/// Existing profiles keep no base point, so they belong to the default one.
+async fn rebuild_profile_table(manager: &SchemaManager<'_>, with_base: bool) -> Result<(), DbErr> {
+    let rebuilt = Alias::new("profile_rebuilt");
+    let columns = [
+        Profile::Id,
+        Profile::NamePrimary,
+        Profile::NameSupplementary,
+        Profile::Avatar,
+        Profile::ClassOf,
+        Profile::UniversityId,
+        Profile::Major,
+        Profile::Bio,
+        Profile::Email,
+        Profile::QQ,
+        Profile::Wechat,
+        Profile::Matrix,
+        Profile::ContactVisibility,
+    ];
+    manager
+        .create_table(profile_table(rebuilt.clone(), with_base))
+        .await?;
+    manager
+        .exec_stmt(
+            Query::insert()
+                .into_table(rebuilt.clone())
+                .columns(columns)
+                .select_from(
+                    Query::select()
+                        .columns(columns)
+                        .from(Profile::Table)
+                        .to_owned(),
+                )
+                .map_err(|err| DbErr::Migration(err.to_string()))?
+                .to_owned(),
+        )
+        .await?;
+    manager
+        .drop_table(Table::drop().table(Profile::Table).to_owned())
+        .await?;
+    manager
+        .rename_table(Table::rename().table(rebuilt, Profile::Table).to_owned())
+        .await
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() == DbBackend::Sqlite {
+            return rebuild_profile_table(manager, true).await;
+        }
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Profile::Table)
+                    .add_column(integer_null(Profile::BaseId))
+                    .to_owned(),
+            )
+            .await?;
+        manager.create_foreign_key(foreign_key()).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() == DbBackend::Sqlite {
+            return rebuild_profile_table(manager, false).await;
+        }
+        manager
+            .drop_foreign_key(
+                ForeignKey::drop()
+                    .name(FOREIGN_KEY)
+                    .table(Profile::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Profile::Table)
+                    .drop_column(Profile::BaseId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,85 @@
+//! Runs every migration up and down again on each supported database
+//!
+//! `SQLite` is always checked in memory, Postgres only when `ALUMNIMAP_TEST_POSTGRES` holds the
+//! URI of a database that may be wiped.
+
+use migration::{Migrator, MigratorTrait, SchemaManager};
+use sea_orm_migration::sea_orm::{ConnectionTrait, Database, DatabaseConnection};
+
+const TABLES: [&str; 5] = ["university", "profile", "account", "session", "base"];
+
+const INSERT_UNIVERSITY: &str =
+    "INSERT INTO university (id, title, icon, colour, longitude, latitude) \
+                                 VALUES (1, 'MIT', 'MIT.png', '#A31F34', -71.09, 42.36)";
+const INSERT_ORPHAN: &str = "INSERT INTO profile (name_primary, avatar, class_of, university_id) \
+                             VALUES ('Nobody', 'nobody.png', 2025, 9)";
+const INSERT_STRAY: &str =
+    "INSERT INTO profile (name_primary, avatar, class_of, university_id, base_id) \
+                             VALUES ('Stray', 'stray.png', 2025, 1, 9)";
+
+async fn check(db: &DatabaseConnection) {
+    Migrator::fresh(db).await.expect("Failed to migrate up");
+    assert!(Migrator::get_pending_migrations(db)
+        .await
+        .expect("Failed to read migration status")
+        .is_empty());
+
+    let manager = SchemaManager::new(db);
+    for table in TABLES {
+        assert!(manager.has_table(table).await.unwrap(), "{table} missing");
+    }
+    assert!(manager
+        .has_column("profile", "contact_visibility")
+        .await
+        .unwrap());
+    assert!(manager.has_column("profile", "base_id").await.unwrap());
+
+    db.execute_unprepared(INSERT_UNIVERSITY).await.unwrap();
+    assert!(
+        db.execute_unprepared(INSERT_ORPHAN).await.is_err(),
+        "profile without university accepted"
+    );
+    assert!(
+        db.execute_unprepared(INSERT_STRAY).await.is_err(),
+        "profile with missing base point accepted"
+    );
+
+    Migrator::reset(db).await.expect("Failed to migrate down");
+    for table in TABLES {
+        assert!(
+            !manager.has_table(table).await.unwrap(),
+            "{table} left over"
+        );
+    }
+}
+
+/// The foreign key cannot be added while profiles refer to missing universities
+async fn check_orphans(db: &DatabaseConnection) {
+    Migrator::fresh(db).await.expect("Failed to migrate up");
+    Migrator::down(db, Some(3))
+        .await
+        .expect("Failed to migrate down");
+    db.execute_unprepared(INSERT_ORPHAN).await.unwrap();
+    assert!(Migrator::up(db, None).await.is_err(), "orphan accepted");
+
+    db.execute_unprepared("DELETE FROM profile").await.unwrap();
+    Migrator::up(db, None).await.expect("Failed to migrate up");
+    Migrator::reset(db).await.expect("Failed to migrate down");
+}
+
+#[async_std::test]
+async fn sqlite() {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    check(&db).await;
+    check_orphans(&db).await;
+}
+
+#[async_std::test]
+async fn postgres() {
+    let Ok(uri) = std::env::var("ALUMNIMAP_TEST_POSTGRES") else {
+        return;
+    };
+    let db = Database::connect(uri).await.unwrap();
+    check(&db).await;
+    check_orphans(&db).await;
+}